@@ -88,6 +88,10 @@ fn parse_line(input: &Vec<char>, start: usize) -> (Packet, usize) {
     return (Packet::List(result), current);
 }
 
+fn parse_packet(input: &str) -> Packet {
+    parse_line(&input.chars().collect(), 0).0
+}
+
 fn parse_packets(input: &str) -> Vec<Packet> {
     let input: Vec<&str> = input.lines().collect();
     let mut packets: Vec<Packet> = vec![];
@@ -120,30 +124,77 @@ fn pair_sum(packets: &Vec<Packet>) -> i32 {
 }
 
 // Part 2
+fn default_dividers() -> Vec<Packet> {
+    vec![parse_packet("[[2]]"), parse_packet("[[6]]")]
+}
+
+// Position (1-based) each divider would take in the sorted list of
+// packets + dividers, computed in a single pass without sorting
+fn divider_ranks(packets: &[Packet], dividers: &[Packet]) -> Vec<usize> {
+    let mut ranks = vec![1; dividers.len()];
+
+    for packet in packets {
+        for (rank, divider) in ranks.iter_mut().zip(dividers) {
+            if packet < divider {
+                *rank += 1;
+            }
+        }
+    }
+
+    // Dividers are part of the list too
+    for (i, divider) in dividers.iter().enumerate() {
+        ranks[i] += dividers
+            .iter()
+            .enumerate()
+            .filter(|(j, other)| *other < divider || (*other == divider && *j < i))
+            .count();
+    }
+
+    ranks
+}
+
+fn decoder_key_with(packets: &[Packet], dividers: &[Packet]) -> usize {
+    divider_ranks(packets, dividers).iter().product()
+}
+
 fn decoder_key(packets: &Vec<Packet>) -> usize {
-    let mut packets = packets.clone();
-    let two = parse_line(&"[[2]]".chars().collect(), 0).0;
-    let six = parse_line(&"[[6]]".chars().collect(), 0).0;
-    packets.push(two.clone());
-    packets.push(six.clone());
-    packets.sort();
+    decoder_key_with(packets, &default_dividers())
+}
 
-    let two_pos = packets.iter().position(|it| it == &two).unwrap() + 1;
-    let six_pos = packets.iter().position(|it| it == &six).unwrap() + 1;
+// Reads `--divider <packet>` (repeatable) from the command line,
+// returning no dividers when none were given
+fn parse_dividers(mut args: impl Iterator<Item = String>) -> Vec<Packet> {
+    let mut dividers = vec![];
 
-    two_pos * six_pos
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--divider" => {
+                let packet = args.next().expect("Missing packet after --divider");
+                dividers.push(parse_packet(&packet));
+            }
+            _ => panic!("Unknown argument: {}", arg),
+        }
+    }
+
+    dividers
 }
 
 fn main() {
     // Parse
     let input = include_str!("../input");
     let packets = parse_packets(input);
+    let dividers = parse_dividers(std::env::args().skip(1));
 
     // Part 1
     println!("Index sum of sorted pairs: {}", pair_sum(&packets));
 
     // Part 2
-    println!("Decoder key: {}", decoder_key(&packets));
+    let key = if dividers.is_empty() {
+        decoder_key(&packets)
+    } else {
+        decoder_key_with(&packets, &dividers)
+    };
+    println!("Decoder key: {}", key);
 }
 #[cfg(test)]
 mod tests {
@@ -270,4 +321,37 @@ mod tests {
 
         assert_eq!(decoder_key(&packets), 140);
     }
+
+    #[test]
+    fn divider_ranks_match_sorting() {
+        let input = include_str!("../example");
+        let packets = parse_packets(input);
+        let dividers = vec![
+            parse_packet("[[6]]"),
+            parse_packet("[[2]]"),
+            parse_packet("[5]"),
+            parse_packet("[[2]]"),
+        ];
+
+        let mut sorted = packets.clone();
+        sorted.extend(dividers.clone());
+        sorted.sort();
+
+        let ranks = divider_ranks(&packets, &dividers);
+        for (rank, divider) in ranks.iter().zip(&dividers) {
+            assert_eq!(&sorted[rank - 1], divider);
+        }
+
+        // Duplicated dividers take distinct positions
+        assert_eq!(ranks, vec![16, 10, 15, 11]);
+    }
+
+    #[test]
+    fn parse_custom_dividers() {
+        let args = ["--divider", "[[3]]", "--divider", "[]"].map(String::from);
+        let dividers = parse_dividers(args.into_iter());
+
+        assert_eq!(dividers, vec![parse_packet("[[3]]"), parse_packet("[]")]);
+        assert!(parse_dividers(std::iter::empty()).is_empty());
+    }
 }
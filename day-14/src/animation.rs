@@ -0,0 +1,23 @@
+use crate::cave::Cave;
+use std::{thread, time::Duration};
+
+/// Redraws the cave in the terminal while it is being simulated
+pub struct Animation {
+    pub every: usize,
+    pub delay: Duration,
+}
+
+impl Animation {
+    pub fn frame(&self, cave: &Cave) {
+        if !cave.ticks.is_multiple_of(self.every) {
+            return;
+        }
+
+        // Clear the screen and move the cursor to the top
+        print!("\x1b[2J\x1b[H");
+        println!("Tick {}, stable: {}", cave.ticks, cave.sands_stable.len());
+        cave.print();
+
+        thread::sleep(self.delay);
+    }
+}
@@ -1,7 +1,32 @@
-#[derive(Clone)]
-pub enum VoidAction {
-    Delete,
-    Keep,
+/// What is below the lowest rock
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Floor {
+    /// Units below the lowest rock fall forever
+    Void,
+    /// Infinite floor at the given depth
+    Infinite(i32),
+    /// Floor at the given depth, closed by walls at the `left` and `right` columns
+    Bounded { left: i32, right: i32, depth: i32 },
+}
+
+impl Floor {
+    pub fn is_solid(&self, pos: &Point) -> bool {
+        match *self {
+            Floor::Void => false,
+            Floor::Infinite(depth) => pos.y >= depth,
+            Floor::Bounded { left, right, depth } => {
+                pos.y >= depth || pos.x <= left || pos.x >= right
+            }
+        }
+    }
+
+    pub fn depth(&self) -> Option<i32> {
+        match *self {
+            Floor::Void => None,
+            Floor::Infinite(depth) => Some(depth),
+            Floor::Bounded { depth, .. } => Some(depth),
+        }
+    }
 }
 
 #[derive(Clone)]
@@ -10,33 +35,44 @@ pub struct Padding {
     pub y: usize,
 }
 
-#[derive(PartialEq, Eq, Debug, Clone)]
-pub struct Block {
-    pub x: usize,
-    pub y: usize,
-    pub kind: BlockKind,
+/// Horizontal position and depth, growing to the right and downwards
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, PartialOrd, Ord)]
+pub struct Point {
+    pub x: i32,
+    pub y: i32,
 }
 
-impl Block {
-    pub fn tuple(&self) -> (usize, usize) {
-        (self.x, self.y)
+impl Point {
+    pub fn new(x: i32, y: i32) -> Self {
+        Self { x, y }
     }
 
-    pub fn normalize(&mut self, x: usize, y: usize, padding_shift: Padding) {
-        self.x -= x;
-        self.x += padding_shift.x;
-        self.y -= y;
-        self.y += padding_shift.y;
+    pub fn shift(&self, dx: i32, dy: i32) -> Self {
+        Self::new(self.x + dx, self.y + dy)
     }
 }
 
-impl From<&str> for Block {
+impl From<&str> for Point {
     fn from(input: &str) -> Self {
         let input: Vec<&str> = input.split(',').collect();
 
         Self {
-            x: input[1].parse().unwrap(),
-            y: input[0].parse().unwrap(),
+            x: input[0].parse().unwrap(),
+            y: input[1].parse().unwrap(),
+        }
+    }
+}
+
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub struct Block {
+    pub pos: Point,
+    pub kind: BlockKind,
+}
+
+impl From<&str> for Block {
+    fn from(input: &str) -> Self {
+        Self {
+            pos: input.into(),
             kind: BlockKind::Rock,
         }
     }
@@ -47,6 +83,15 @@ pub enum BlockKind {
     Rock,
     SandSource,
     SandUnit,
+    WaterSource,
+    WaterUnit(Flow),
+}
+
+/// Direction a water unit is spreading to when it can't fall anymore
+#[derive(PartialEq, Eq, Debug, Clone)]
+pub enum Flow {
+    Left,
+    Right,
 }
 
 impl BlockKind {
@@ -55,14 +100,89 @@ impl BlockKind {
             BlockKind::Rock => '#',
             BlockKind::SandSource => '+',
             BlockKind::SandUnit => 'o',
+            BlockKind::WaterSource => '%',
+            BlockKind::WaterUnit(_) => '~',
+        }
+    }
+
+    /// Particle produced by a source block
+    pub fn particle(&self) -> Option<BlockKind> {
+        match self {
+            BlockKind::SandSource => Some(BlockKind::SandUnit),
+            BlockKind::WaterSource => Some(BlockKind::WaterUnit(Flow::Left)),
+            _ => None,
+        }
+    }
+
+    /// Next position and state of a particle, or `None` when it comes to rest.
+    ///
+    /// Sand falls down, then diagonally left, then diagonally right.
+    /// Water falls like sand, but once it can't fall it spreads sideways:
+    /// first to the left and then, when blocked, back to the right.
+    pub fn next_move(
+        &self,
+        pos: Point,
+        is_free: impl Fn(&Point) -> bool,
+    ) -> Option<(Point, BlockKind)> {
+        let fall = [pos.shift(0, 1), pos.shift(-1, 1), pos.shift(1, 1)];
+
+        match self {
+            BlockKind::SandUnit => fall
+                .into_iter()
+                .find(|pos| is_free(pos))
+                .map(|pos| (pos, BlockKind::SandUnit)),
+            BlockKind::WaterUnit(flow) => {
+                if let Some(pos) = fall.into_iter().find(|pos| is_free(pos)) {
+                    return Some((pos, BlockKind::WaterUnit(Flow::Left)));
+                }
+
+                let left = pos.shift(-1, 0);
+                let right = pos.shift(1, 0);
+                if *flow == Flow::Left && is_free(&left) {
+                    return Some((left, BlockKind::WaterUnit(Flow::Left)));
+                }
+                if is_free(&right) {
+                    return Some((right, BlockKind::WaterUnit(Flow::Right)));
+                }
+
+                None
+            }
+            _ => None,
         }
     }
 }
 
 pub fn parse_line(input: &str) -> Vec<Block> {
-    input
-        .split(" -> ")
-        .into_iter()
-        .map(|it| Block::from(it))
-        .collect()
+    input.split(" -> ").map(Block::from).collect()
+}
+
+/// Every point covered by a rock path, lines must be straight or at 45 degrees
+pub fn rock_points(path: &[Block]) -> Vec<Point> {
+    let mut points = vec![];
+
+    path.windows(2).for_each(|pos| {
+        let (from, to) = (pos[0].pos, pos[1].pos);
+        let (dx, dy) = (to.x - from.x, to.y - from.y);
+
+        if dx != 0 && dy != 0 && dx.abs() != dy.abs() {
+            panic!("Unsupported rock line: {:?} -> {:?}", from, to);
+        }
+
+        let steps = dx.abs().max(dy.abs());
+        for i in 0..=steps {
+            points.push(from.shift(dx.signum() * i, dy.signum() * i));
+        }
+    });
+
+    // Single point paths
+    if path.len() == 1 {
+        points.push(path[0].pos);
+    }
+
+    points
+}
+
+/// Depth of the lowest rock
+pub fn lowest_rock(rocks: &[Vec<Block>]) -> i32 {
+    rocks.iter().flatten().map(|it| it.pos.y).max().unwrap_or(0)
 }
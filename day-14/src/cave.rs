@@ -3,96 +3,50 @@ use std::collections::{HashMap, HashSet};
 
 pub struct Cave {
     pub padding: Padding,
-    pub blocks: HashMap<(usize, usize), BlockKind>,
-    pub unstable_blocks: HashMap<(usize, usize), BlockKind>,
-    pub ground_pos: usize,
-    sand_producer: Block,
+    pub blocks: HashMap<Point, BlockKind>,
+    pub unstable_blocks: HashMap<Point, BlockKind>,
+    pub floor: Floor,
+    pub lowest_rock: i32,
+    sand_producers: Vec<Block>,
     pub last_tick_produced: usize,
     pub last_tick_moved: usize,
     pub ticks: usize,
     pub void_reached: bool,
-    pub sands_stable: HashSet<(usize, usize)>,
-    pub void_action: VoidAction,
+    /// Water spread over an infinite floor and keeps flowing away
+    pub drained: bool,
+    pub sands_stable: HashSet<Point>,
 }
 
 impl Cave {
     pub fn new(
         rocks: Vec<Vec<Block>>,
-        sand_source: Block,
+        sand_sources: Vec<Block>,
         padding: Padding,
-        void_action: VoidAction,
+        floor: Floor,
     ) -> Self {
         // Initialize block map
-        let mut blocks: HashMap<(usize, usize), BlockKind> = HashMap::new();
-
-        // Calculate min and max for x,y
-        let mut x = (sand_source.x, sand_source.x);
-        let mut y = (sand_source.y, sand_source.y);
-
-        rocks.iter().flatten().for_each(|it| {
-            if it.x < x.0 {
-                x.0 = it.x;
-            }
-            if it.x > x.1 {
-                x.1 = it.x;
-            }
-
-            if it.y < y.0 {
-                y.0 = it.y;
-            }
-            if it.y > y.1 {
-                y.1 = it.y;
-            }
-        });
+        let mut blocks: HashMap<Point, BlockKind> = HashMap::new();
 
         // Add rock lines
-        rocks.iter().for_each(|lines| {
-            lines
-                .windows(2)
-                .for_each(|pos| match (pos[0].tuple(), pos[1].tuple()) {
-                    // Horizontal
-                    ((a, b), (c, d)) if a == c => {
-                        if b > d {
-                            for i in (d..=b).rev() {
-                                blocks.insert((a, i), BlockKind::Rock);
-                            }
-                        } else {
-                            for i in b..=d {
-                                blocks.insert((a, i), BlockKind::Rock);
-                            }
-                        }
-                    }
-                    // Vertical
-                    ((a, b), (c, d)) if b == d => {
-                        if a > c {
-                            for i in (c..=a).rev() {
-                                blocks.insert((i, b), BlockKind::Rock);
-                            }
-                        } else {
-                            for i in a..=c {
-                                blocks.insert((i, b), BlockKind::Rock);
-                            }
-                        }
-                    }
-                    _ => unreachable!("Diagonal lines not supported: {:?} -> {:?}", pos[0], pos[1]),
-                });
+        rocks.iter().for_each(|path| {
+            rock_points(path).into_iter().for_each(|pos| {
+                blocks.insert(pos, BlockKind::Rock);
+            });
         });
 
-        // Ground
-        let ground_pos = x.1 + 2;
-
         Self {
-            ground_pos,
+            lowest_rock: lowest_rock(&rocks),
+            floor,
             last_tick_moved: 0,
             last_tick_produced: 0,
             ticks: 0,
             padding,
-            sand_producer: sand_source.clone(),
+            sand_producers: sand_sources,
             void_reached: false,
+            drained: false,
             sands_stable: HashSet::new(),
             blocks,
             unstable_blocks: HashMap::new(),
-            void_action,
         }
     }
 
@@ -100,95 +54,79 @@ impl Cave {
         self.last_tick_moved == 0 && self.last_tick_produced == 0 && self.ticks > 0
     }
 
-    fn tick_move(&mut self) {
-        let mut swap: Vec<((usize, usize), (usize, usize))> = vec![];
-        let mut stable: Vec<((usize, usize), BlockKind)> = vec![];
-
-        self.unstable_blocks.iter().for_each(|(pos, kind)| {
-            let x = pos.0;
-            let y = pos.1;
-
-            if pos.0 >= self.ground_pos && matches!(self.void_action, VoidAction::Keep) {
-                stable.push((*pos, kind.clone()));
-
-                return;
-            }
-
-            match kind {
-                &BlockKind::SandUnit => {
-                    // Move down
-                    if let None = self.blocks.get(&(x + 1, y)) {
-                        swap.push(((x, y), (x + 1, y)));
-
-                        return;
-                    }
-
-                    // Move diagonally left
-                    if let None = self.blocks.get(&(x + 1, y - 1)) {
-                        swap.push(((x, y), (x + 1, y - 1)));
+    fn is_solid(&self, pos: &Point) -> bool {
+        self.blocks.contains_key(pos) || self.floor.is_solid(pos)
+    }
 
-                        return;
-                    }
+    fn is_free(&self, pos: &Point) -> bool {
+        !self.is_solid(pos) && !self.unstable_blocks.contains_key(pos)
+    }
 
-                    // Move diagonally right
-                    if let None = self.blocks.get(&(x + 1, y + 1)) {
-                        swap.push(((x, y), (x + 1, y + 1)));
+    /// Without a floor, anything below the lowest rock falls forever
+    fn in_void(&self, pos: &Point) -> bool {
+        self.floor == Floor::Void && pos.y > self.lowest_rock
+    }
 
-                        return;
-                    }
+    fn settle(&mut self, pos: Point, kind: BlockKind) {
+        self.unstable_blocks.remove(&pos);
+        self.blocks.insert(pos, kind);
+        self.sands_stable.insert(pos);
+    }
 
-                    // Stable
-                    stable.push((*pos, kind.clone()));
-                    if self.sands_stable.get(pos).is_none() {
-                        self.sands_stable.insert(*pos);
-                    }
+    fn tick_move(&mut self) {
+        // Move the deepest particles first so they make room for the ones above
+        let mut particles: Vec<(Point, BlockKind)> = self
+            .unstable_blocks
+            .iter()
+            .map(|(pos, kind)| (*pos, kind.clone()))
+            .collect();
+        particles.sort_unstable_by_key(|(pos, _)| std::cmp::Reverse((pos.y, pos.x)));
+
+        for (pos, kind) in particles {
+            let (new, new_kind) = match kind.next_move(pos, |it| self.is_free(it)) {
+                Some(next) => next,
+                None => {
+                    self.settle(pos, kind);
+
+                    continue;
                 }
-                _ => {}
-            }
-        });
+            };
 
-        // Remove stable blocks
-        stable.iter().for_each(|(pos, kind)| {
-            self.unstable_blocks.remove(pos);
-            self.blocks.insert(*pos, kind.clone());
-        });
+            self.last_tick_moved += 1;
+            self.unstable_blocks.remove(&pos);
 
-        // Move blocks
-        self.last_tick_moved += swap.len();
-        swap.iter().for_each(|(current, new)| {
             // Void reached
-            match &self.void_action {
-                VoidAction::Delete => {
-                    if new.0 >= self.ground_pos {
-                        self.void_reached = true;
+            if self.in_void(&new) {
+                self.void_reached = true;
 
-                        // Remove
-                        self.blocks.remove(&current);
-                        self.unstable_blocks.remove(&current);
-                        self.sands_stable.remove(current);
+                continue;
+            }
 
-                        return;
-                    }
-                }
+            // Nothing stops water spreading over an infinite floor, drain it
+            let spreading = new.y == pos.y && self.floor.is_solid(&new.shift(0, 1));
+            if spreading && matches!(self.floor, Floor::Infinite(_)) {
+                self.drained = true;
 
-                _ => {}
+                continue;
             }
 
             // Move
-            self.unstable_blocks.insert(*new, BlockKind::SandUnit);
-            self.unstable_blocks.remove(current);
-        });
+            self.unstable_blocks.insert(new, new_kind);
+        }
     }
 
     fn tick_produce(&mut self) {
-        // Produce more sand
-        let x = self.sand_producer.x;
-        let y = self.sand_producer.y;
-
-        // Current block is empty push a new sand unit
-        if let None = self.blocks.get(&(x, y)) {
-            self.unstable_blocks.insert((x, y), BlockKind::SandUnit);
-            self.last_tick_produced += 1;
+        // Produce more particles on every free source
+        for i in 0..self.sand_producers.len() {
+            let source = &self.sand_producers[i];
+            let pos = source.pos;
+
+            if let Some(particle) = source.kind.particle() {
+                if self.is_free(&pos) {
+                    self.unstable_blocks.insert(pos, particle);
+                    self.last_tick_produced += 1;
+                }
+            }
         }
     }
 
@@ -211,77 +149,133 @@ impl Cave {
         }
     }
 
-    pub fn grid(&self) -> Vec<Vec<char>> {
-        // Clone producer
-        let mut sand_source = self.sand_producer.clone();
+    /// Drops every sand unit from the first source along a remembered path.
+    ///
+    /// Instead of producing one grain and moving it tick by tick, the path taken by
+    /// the previous grain is kept in a stack and the next grain resumes from the parent
+    /// of the position where the last one settled, since everything above it is
+    /// guaranteed to be the same.
+    pub fn drop_all(&mut self) {
+        let source = self.sand_producers[0].pos;
+        let mut path = vec![source];
+
+        if self.is_solid(&source) {
+            return;
+        }
+
+        while let Some(&pos) = path.last() {
+            // Falling into the void
+            if self.in_void(&pos) {
+                self.void_reached = true;
+
+                return;
+            }
+
+            let next = [pos.shift(0, 1), pos.shift(-1, 1), pos.shift(1, 1)]
+                .into_iter()
+                .find(|it| !self.is_solid(it));
+
+            match next {
+                Some(next) => path.push(next),
+                None => {
+                    path.pop();
+                    self.settle(pos, BlockKind::SandUnit);
+                }
+            }
+        }
+    }
+
+    /// Counts the stable sand units without simulating them, `None` without a floor.
+    ///
+    /// With a floor, sand fills a triangle below the first source except for rocks
+    /// and the cells "shadowed" by them: a cell gets sand only if any of the three
+    /// cells above it did.
+    pub fn count_closed_form(&self) -> Option<usize> {
+        let source = self.sand_producers[0].pos;
+        let depth = self.floor.depth()?;
+        let mut count = 0;
+        let mut previous: Vec<bool> = vec![];
+
+        for (k, y) in (source.y..depth).enumerate() {
+            // Row `k` of the triangle covers `source.x - k..=source.x + k`
+            let row: Vec<bool> = (0..=2 * k)
+                .map(|i| {
+                    let pos = Point::new(source.x + i as i32 - k as i32, y);
+                    if self.is_solid(&pos) {
+                        return false;
+                    }
+
+                    if k == 0 {
+                        return true;
+                    }
+
+                    // Same column in the previous row is at `i - 1`
+                    (i.saturating_sub(2)..=i.min(2 * k - 2)).any(|j| previous[j])
+                })
+                .collect();
 
+            count += row.iter().filter(|it| **it).count();
+            previous = row;
+        }
+
+        Some(count)
+    }
+
+    pub fn grid(&self) -> Vec<Vec<char>> {
         // Calculate min and max for x,y
-        let mut x = (sand_source.x, sand_source.x);
-        let mut y = (sand_source.y, sand_source.y);
+        let first = self.sand_producers[0].pos;
+        let mut x = (first.x, first.x);
+        let mut y = (first.y, first.y);
         let padding_shift = Padding {
             x: self.padding.x / 2,
             y: self.padding.y / 2,
         };
 
-        self.blocks.iter().for_each(|(pos, _)| {
-            if pos.0 < x.0 {
-                x.0 = pos.0;
-            }
-            if pos.0 > x.1 {
-                x.1 = pos.0;
-            }
-
-            if pos.1 < y.0 {
-                y.0 = pos.1;
-            }
-            if pos.1 > y.1 {
-                y.1 = pos.1;
-            }
-        });
+        let sources = self.sand_producers.iter().map(|it| (it.pos, &it.kind));
+        let objects = sources
+            .chain(self.blocks.iter().map(|(pos, kind)| (*pos, kind)))
+            .chain(self.unstable_blocks.iter().map(|(pos, kind)| (*pos, kind)));
 
-        // Normalize
-        let x_norm = x.0;
-        let y_norm = y.0;
-
-        let mut objects: Vec<((usize, usize), BlockKind)> = vec![];
-        self.blocks.iter().for_each(|(pos, kind)| {
-            let mut new_pos = pos.clone();
-            new_pos.0 -= x_norm;
-            new_pos.0 += padding_shift.x;
-            new_pos.1 -= y_norm;
-            new_pos.1 += padding_shift.y;
-
-            objects.push((new_pos, kind.clone()));
+        objects.clone().for_each(|(pos, _)| {
+            x = (x.0.min(pos.x), x.1.max(pos.x));
+            y = (y.0.min(pos.y), y.1.max(pos.y));
         });
 
         // Initialize grid with air blocks
-        let m = x.1 - x_norm;
-        let n = y.1 - y_norm;
-        let mut grid = vec![vec!['.'; n + self.padding.y]; m + self.padding.x];
+        let m = (x.1 - x.0) as usize;
+        let n = (y.1 - y.0) as usize;
+        let mut grid = vec![vec!['.'; m + self.padding.x]; n + self.padding.y];
 
-        // Normalize and add sand source
-        sand_source.normalize(x_norm, y_norm, padding_shift);
-        grid[sand_source.x][sand_source.y] = BlockKind::SandSource.char();
+        // Normalize
+        let origin = Point::new(x.0 - padding_shift.x as i32, y.0 - padding_shift.y as i32);
+
+        // Add floor and walls
+        grid.iter_mut().enumerate().for_each(|(j, cols)| {
+            cols.iter_mut().enumerate().for_each(|(i, val)| {
+                if self.floor.is_solid(&origin.shift(i as i32, j as i32)) {
+                    *val = BlockKind::Rock.char();
+                }
+            });
+        });
 
         // Add objects
-        objects.iter().for_each(|(pos, kind)| {
-            grid[pos.0][pos.1] = kind.char();
+        objects.for_each(|(pos, kind)| {
+            grid[(pos.y - origin.y) as usize][(pos.x - origin.x) as usize] = kind.char();
         });
 
         grid
     }
 
-    pub fn print(&self) {
-        let grid = self.grid();
-
-        println!("");
-        for cols in grid.iter() {
-            let mut line = "".to_string();
-            for val in cols {
-                line = format!("{}{}", line, val);
-            }
+    pub fn render(&self) -> String {
+        self.grid()
+            .iter()
+            .map(|cols| cols.iter().collect::<String>())
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
 
-            println!("{}", line);
-        }
+    pub fn print(&self) {
+        println!();
+        println!("{}", self.render());
     }
 }
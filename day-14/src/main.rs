@@ -1,59 +1,196 @@
+mod animation;
 mod block;
 mod cave;
 
+use animation::*;
 use block::*;
 use cave::*;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq, Eq)]
+enum Engine {
+    /// Moves every unit one step per tick, needed for animations
+    Tick,
+    /// Drops units along a remembered path
+    Path,
+    /// Counts units without simulating them, needs a `Floor::Infinite` or
+    /// `Floor::Bounded` and falls back to `drop_all` over `Floor::Void`
+    ClosedForm,
+}
+
+struct Options {
+    engine: Engine,
+    animation: Option<Animation>,
+    sources: Vec<i32>,
+    particle: BlockKind,
+    floor_depth: Option<i32>,
+    walls: Option<(i32, i32)>,
+}
+
+impl Options {
+    /// Reads `--engine <tick|path|closed-form>`, `--animate <ticks>`, `--speed <ms>`,
+    /// `--source <column>` (repeatable), `--water`, `--floor <depth>` and
+    /// `--walls <left>,<right>` from the command line.
+    /// Animations, water and several sources always use the tick engine.
+    fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut engine = Engine::Path;
+        let mut every: Option<usize> = None;
+        let mut delay = Duration::from_millis(50);
+        let mut sources = vec![];
+        let mut particle = BlockKind::SandSource;
+        let mut floor_depth = None;
+        let mut walls = None;
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let mut value = || {
+                args.next()
+                    .unwrap_or_else(|| panic!("Missing value after {}", arg))
+            };
+
+            match arg.as_str() {
+                "--engine" => {
+                    engine = match value().as_str() {
+                        "tick" => Engine::Tick,
+                        "path" => Engine::Path,
+                        "closed-form" => Engine::ClosedForm,
+                        other => panic!("Unknown engine: {}", other),
+                    }
+                }
+                "--animate" => every = Some(value().parse().expect("Invalid tick count")),
+                "--speed" => delay = Duration::from_millis(value().parse().expect("Invalid speed")),
+                "--source" => sources.push(value().parse().expect("Invalid source column")),
+                "--water" => particle = BlockKind::WaterSource,
+                "--floor" => floor_depth = Some(value().parse().expect("Invalid floor depth")),
+                "--walls" => {
+                    let pos = Point::from(value().as_str());
+                    walls = Some((pos.x, pos.y));
+                }
+                _ => panic!("Unknown argument: {}", arg),
+            }
+        }
+
+        if sources.is_empty() {
+            sources.push(500);
+        }
+
+        // The other engines only drop units from the first source
+        if every.is_some() || particle != BlockKind::SandSource || sources.len() > 1 {
+            engine = Engine::Tick;
+        }
+
+        Self {
+            engine,
+            animation: every.map(|every| Animation {
+                every: every.max(1),
+                delay,
+            }),
+            sources,
+            particle,
+            floor_depth,
+            walls,
+        }
+    }
+
+    fn sources(&self) -> Vec<Block> {
+        self.sources
+            .iter()
+            .map(|x| Block {
+                pos: Point::new(*x, 0),
+                kind: self.particle.clone(),
+            })
+            .collect()
+    }
+
+    /// Floor for part 2, two units below the lowest rock unless configured
+    fn floor(&self, rocks: &[Vec<Block>]) -> Floor {
+        let depth = self.floor_depth.unwrap_or(lowest_rock(rocks) + 2);
+
+        match self.walls {
+            Some((left, right)) => Floor::Bounded { left, right, depth },
+            None => Floor::Infinite(depth),
+        }
+    }
+}
+
+fn simulate(cave: &mut Cave, animation: &Option<Animation>) {
+    while !cave.stable() && !cave.void_reached && !cave.drained {
+        cave.tick();
+
+        if let Some(animation) = animation {
+            animation.frame(cave);
+        }
+    }
+}
+
+fn stable_sands(cave: &mut Cave, options: &Options) -> usize {
+    match options.engine {
+        Engine::ClosedForm if cave.floor != Floor::Void => {
+            return cave.count_closed_form().unwrap();
+        }
+        Engine::Path | Engine::ClosedForm => cave.drop_all(),
+        Engine::Tick => simulate(cave, &options.animation),
+    }
+
+    cave.sands_stable.len()
+}
 
 fn main() {
     let input = include_str!("../input");
-    let positions: Vec<Vec<Block>> = input.lines().map(|it| parse_line(it)).collect();
+    let positions: Vec<Vec<Block>> = input.lines().map(parse_line).collect();
+    let options = Options::from_args(std::env::args().skip(1));
 
     // Part 1
     let mut cave = Cave::new(
         positions.clone(),
-        Block {
-            x: 0,
-            y: 500,
-            kind: BlockKind::SandSource,
-        },
-        Padding { x: 5, y: 20 },
-        VoidAction::Delete,
+        options.sources(),
+        Padding { x: 20, y: 5 },
+        Floor::Void,
     );
 
-    while !cave.stable() && !cave.void_reached {
-        cave.tick();
-    }
-
-    println!("Part 1, stable sands: {}", cave.sands_stable.len());
+    println!(
+        "Part 1, stable sands: {}",
+        stable_sands(&mut cave, &options)
+    );
 
     // Part 2
+    let floor = options.floor(&positions);
     let mut cave = Cave::new(
         positions,
-        Block {
-            x: 0,
-            y: 500,
-            kind: BlockKind::SandSource,
-        },
-        Padding { x: 5, y: 200 },
-        VoidAction::Keep,
+        options.sources(),
+        Padding { x: 200, y: 5 },
+        floor,
     );
 
-    while !cave.stable() {
-        cave.tick();
-    }
-
-    println!("Part 2, stable sands: {}", cave.sands_stable.len());
+    println!(
+        "Part 2, stable sands: {}",
+        stable_sands(&mut cave, &options)
+    );
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn source(x: i32) -> Block {
+        Block {
+            pos: Point::new(x, 0),
+            kind: BlockKind::SandSource,
+        }
+    }
+
+    fn example_cave(floor: Floor) -> Cave {
+        let input = include_str!("../example");
+        let positions: Vec<Vec<Block>> = input.lines().map(parse_line).collect();
+
+        Cave::new(positions, vec![source(500)], Padding { x: 10, y: 2 }, floor)
+    }
+
     #[test]
     fn parse_position() {
         let input: Block = "498,4".into();
-        assert_eq!(input.y, 498);
-        assert_eq!(input.x, 4);
+        assert_eq!(input.pos.x, 498);
+        assert_eq!(input.pos.y, 4);
     }
 
     #[test]
@@ -63,48 +200,284 @@ mod tests {
         assert_eq!(
             positions[0],
             Block {
-                y: 498,
-                x: 4,
+                pos: Point::new(498, 4),
                 kind: BlockKind::Rock
             }
         );
         assert_eq!(
             positions[1],
             Block {
-                y: 498,
-                x: 6,
+                pos: Point::new(498, 6),
                 kind: BlockKind::Rock
             }
         );
         assert_eq!(
             positions[2],
             Block {
-                y: 496,
-                x: 6,
+                pos: Point::new(496, 6),
                 kind: BlockKind::Rock
             }
         );
     }
 
+    #[test]
+    fn diagonal_rocks() {
+        let points = rock_points(&parse_line("500,2 -> 502,4 -> 500,6"));
+        let expected = [(500, 2), (501, 3), (502, 4), (502, 4), (501, 5), (500, 6)];
+
+        assert_eq!(points, expected.map(|(x, y)| Point::new(x, y)).to_vec());
+    }
+
+    #[test]
+    #[should_panic(expected = "Unsupported rock line")]
+    fn skewed_rocks() {
+        rock_points(&parse_line("500,2 -> 501,4"));
+    }
+
     #[test]
     fn example() {
+        let mut cave = example_cave(Floor::Void);
+
+        simulate(&mut cave, &None);
+
+        assert_eq!(cave.sands_stable.len(), 24);
+    }
+
+    #[test]
+    fn example_floor() {
+        let mut cave = example_cave(Floor::Infinite(11));
+
+        simulate(&mut cave, &None);
+
+        assert_eq!(cave.sands_stable.len(), 93);
+        assert!(cave.sands_stable.iter().all(|it| it.y < 11));
+    }
+
+    #[test]
+    fn example_walls() {
+        let walls = Floor::Bounded {
+            left: 494,
+            right: 504,
+            depth: 11,
+        };
+        let mut ticked = example_cave(walls.clone());
+        simulate(&mut ticked, &None);
+
+        let mut dropped = example_cave(walls.clone());
+        dropped.drop_all();
+
+        assert_eq!(dropped.sands_stable, ticked.sands_stable);
+        assert_eq!(
+            example_cave(walls).count_closed_form(),
+            Some(ticked.sands_stable.len())
+        );
+        assert!(ticked
+            .sands_stable
+            .iter()
+            .all(|it| it.x > 494 && it.x < 504));
+    }
+
+    #[test]
+    fn engines_match_example() {
+        for floor in [Floor::Void, Floor::Infinite(11)] {
+            let mut ticked = example_cave(floor.clone());
+            simulate(&mut ticked, &None);
+
+            let mut dropped = example_cave(floor);
+            dropped.drop_all();
+
+            assert_eq!(dropped.sands_stable, ticked.sands_stable);
+            assert_eq!(dropped.void_reached, ticked.void_reached);
+        }
+
+        let cave = example_cave(Floor::Infinite(11));
+        assert_eq!(cave.count_closed_form(), Some(93));
+        assert_eq!(example_cave(Floor::Void).count_closed_form(), None);
+    }
+
+    #[test]
+    fn engines_match_input() {
+        let input = include_str!("../input");
+        let positions: Vec<Vec<Block>> = input.lines().map(parse_line).collect();
+        let depth = lowest_rock(&positions) + 2;
+        let cave = |floor| {
+            Cave::new(
+                positions.clone(),
+                vec![source(500)],
+                Padding { x: 20, y: 5 },
+                floor,
+            )
+        };
+
+        let mut ticked = cave(Floor::Void);
+        simulate(&mut ticked, &None);
+        let mut dropped = cave(Floor::Void);
+        dropped.drop_all();
+        assert_eq!(dropped.sands_stable, ticked.sands_stable);
+
+        let mut dropped = cave(Floor::Infinite(depth));
+        dropped.drop_all();
+        assert_eq!(
+            Some(dropped.sands_stable.len()),
+            cave(Floor::Infinite(depth)).count_closed_form()
+        );
+    }
+
+    #[test]
+    fn water_spreads_sideways() {
+        // A 3 wide basin
+        let rocks = vec![parse_line("10,3 -> 10,5 -> 14,5 -> 14,3")];
+        let mut cave = Cave::new(
+            rocks,
+            vec![Block {
+                pos: Point::new(12, 0),
+                kind: BlockKind::WaterSource,
+            }],
+            Padding { x: 2, y: 2 },
+            Floor::Void,
+        );
+
+        while cave.ticks < 3 {
+            cave.tick();
+        }
+
+        // Sand would pile up in the middle, water fills the bottom row
+        for x in 11..=13 {
+            let pos = Point::new(x, 4);
+            assert!(cave.sands_stable.contains(&pos), "{:?} not filled", pos);
+        }
+    }
+
+    #[test]
+    fn water_drains_on_infinite_floor() {
+        let mut cave = Cave::new(
+            vec![],
+            vec![Block {
+                pos: Point::new(0, 0),
+                kind: BlockKind::WaterSource,
+            }],
+            Padding { x: 2, y: 2 },
+            Floor::Infinite(3),
+        );
+
+        while cave.ticks < 10 {
+            cave.tick();
+        }
+
+        assert!(cave.sands_stable.is_empty());
+    }
+
+    #[test]
+    fn water_simulation_ends_on_infinite_floor() {
         let input = include_str!("../example");
-        let positions: Vec<Vec<Block>> = input.lines().map(|it| parse_line(it)).collect();
+        let positions: Vec<Vec<Block>> = input.lines().map(parse_line).collect();
         let mut cave = Cave::new(
             positions,
-            Block {
-                x: 0,
-                y: 500,
-                kind: BlockKind::SandSource,
-            },
-            Padding { x: 2, y: 10 },
-            VoidAction::Delete,
+            vec![Block {
+                pos: Point::new(500, 0),
+                kind: BlockKind::WaterSource,
+            }],
+            Padding { x: 10, y: 2 },
+            Floor::Infinite(11),
         );
 
-        while !cave.stable() && !cave.void_reached {
-            cave.tick();
+        simulate(&mut cave, &None);
+
+        assert!(cave.drained);
+        assert!(!cave.void_reached);
+    }
+
+    #[test]
+    fn multiple_sources() {
+        let input = include_str!("../example");
+        let positions: Vec<Vec<Block>> = input.lines().map(parse_line).collect();
+        let mut cave = Cave::new(
+            positions,
+            vec![source(497), source(500)],
+            Padding { x: 10, y: 2 },
+            Floor::Infinite(11),
+        );
+
+        simulate(&mut cave, &None);
+
+        // Both sources end up buried
+        assert!(cave.blocks.contains_key(&Point::new(497, 0)));
+        assert!(cave.blocks.contains_key(&Point::new(500, 0)));
+    }
+
+    #[test]
+    fn engines_match_multiple_sources() {
+        let input = include_str!("../example");
+        let positions: Vec<Vec<Block>> = input.lines().map(parse_line).collect();
+
+        for engine in ["path", "closed-form"] {
+            let args = ["--engine", engine, "--source", "497", "--source", "500"];
+            let options = Options::from_args(args.map(String::from).into_iter());
+            assert_eq!(options.engine, Engine::Tick);
+
+            for floor in [Floor::Void, Floor::Infinite(11)] {
+                let cave = || {
+                    Cave::new(
+                        positions.clone(),
+                        options.sources(),
+                        Padding { x: 10, y: 2 },
+                        floor.clone(),
+                    )
+                };
+
+                let mut ticked = cave();
+                simulate(&mut ticked, &None);
+
+                assert_eq!(
+                    stable_sands(&mut cave(), &options),
+                    ticked.sands_stable.len()
+                );
+            }
         }
+    }
 
-        assert_eq!(cave.sands_stable.len(), 24);
+    #[test]
+    fn options_args() {
+        let args = [
+            "--animate",
+            "10",
+            "--speed",
+            "5",
+            "--source",
+            "490",
+            "--water",
+            "--floor",
+            "20",
+            "--walls",
+            "480,520",
+        ];
+        let options = Options::from_args(args.map(String::from).into_iter());
+
+        assert_eq!(options.sources, vec![490]);
+        assert_eq!(options.particle, BlockKind::WaterSource);
+        assert_eq!(options.engine, Engine::Tick);
+        assert_eq!(
+            options.floor(&[]),
+            Floor::Bounded {
+                left: 480,
+                right: 520,
+                depth: 20
+            }
+        );
+
+        let animation = options.animation.unwrap();
+        assert_eq!(animation.every, 10);
+        assert_eq!(animation.delay, Duration::from_millis(5));
+
+        let rocks = vec![parse_line("498,4 -> 498,6")];
+        let options = Options::from_args(std::iter::empty());
+        assert!(options.animation.is_none());
+        assert_eq!(options.engine, Engine::Path);
+        assert_eq!(options.floor(&rocks), Floor::Infinite(8));
+
+        let args = ["--engine", "closed-form"].map(String::from);
+        let options = Options::from_args(args.into_iter());
+        assert_eq!(options.engine, Engine::ClosedForm);
+        assert_eq!(options.sources, vec![500]);
     }
 }
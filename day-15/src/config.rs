@@ -0,0 +1,116 @@
+use std::fs;
+use std::ops::Range;
+use std::str::FromStr;
+
+/// Puzzle parameters that change between the example and the real input
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    /// Path of the input, the embedded real input when `None`
+    pub input: Option<String>,
+    /// Row to count the positions where a beacon can't be
+    pub row: i32,
+    /// Smallest `x` and `y` of the search area
+    pub min: i32,
+    /// Largest `x` and `y` of the search area, inclusive
+    pub max: i32,
+    /// Multiplier applied to `x` by the tuning frequency
+    pub multiplier: i64,
+    /// Where to write an SVG image of the sensors, if anywhere
+    pub svg: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            input: None,
+            row: 2_000_000,
+            min: 0,
+            max: 4_000_000,
+            multiplier: 4_000_000,
+            svg: None,
+        }
+    }
+}
+
+impl Config {
+    /// Reads `--input <path>`, `--row <y>`, `--min <n>`, `--max <n>`, `--multiplier <n>`
+    /// and `--svg <path>`.
+    ///
+    /// When an input is given, `<path>.config` is loaded too if it exists,
+    /// command line arguments take precedence over it.
+    pub fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut overrides: Vec<(String, String)> = vec![];
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let key = arg
+                .strip_prefix("--")
+                .unwrap_or_else(|| panic!("Unknown argument: {}", arg));
+            let value = args
+                .next()
+                .unwrap_or_else(|| panic!("Missing value after {}", arg));
+
+            overrides.push((key.to_string(), value));
+        }
+
+        let mut config = Config::default();
+
+        if let Some((_, input)) = overrides.iter().find(|(key, _)| key == "input") {
+            if let Ok(file) = fs::read_to_string(format!("{}.config", input)) {
+                config.apply_file(&file);
+            }
+        }
+
+        for (key, value) in overrides {
+            config.set(&key, &value);
+        }
+
+        config
+    }
+
+    /// Applies `key = value` lines, empty lines and `#` comments are skipped
+    pub fn apply_file(&mut self, input: &str) {
+        input
+            .lines()
+            .map(|it| it.trim())
+            .filter(|it| !it.is_empty() && !it.starts_with('#'))
+            .for_each(|line| {
+                let (key, value) = line
+                    .split_once('=')
+                    .unwrap_or_else(|| panic!("Invalid config line: {}", line));
+
+                self.set(key.trim(), value.trim());
+            });
+    }
+
+    pub fn set(&mut self, key: &str, value: &str) {
+        match key {
+            "input" => self.input = Some(value.to_string()),
+            "row" => self.row = parse(key, value),
+            "min" => self.min = parse(key, value),
+            "max" => self.max = parse(key, value),
+            "multiplier" => self.multiplier = parse(key, value),
+            "svg" => self.svg = Some(value.to_string()),
+            _ => panic!("Unknown config key: {}", key),
+        }
+    }
+
+    /// Search area along either axis
+    pub fn area(&self) -> Range<i32> {
+        self.min..self.max + 1
+    }
+
+    pub fn read_input(&self) -> String {
+        match &self.input {
+            Some(path) => fs::read_to_string(path)
+                .unwrap_or_else(|err| panic!("Couldn't read {}: {}", path, err)),
+            None => include_str!("../input").to_string(),
+        }
+    }
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> T {
+    value
+        .parse()
+        .unwrap_or_else(|_| panic!("Invalid value for {}: {}", key, value))
+}
@@ -1,42 +1,47 @@
-use std::ops::Range;
-
+mod config;
 mod point;
+mod render;
 mod scanner;
 mod sensor;
 
+use config::*;
 use scanner::*;
 use sensor::*;
 
 fn main() {
-    let input = include_str!("../input");
+    let config = Config::from_args(std::env::args().skip(1));
+    let input = config.read_input();
     let sensors: Vec<Sensor> = input.lines().map(|it| it.into()).collect();
 
     // Part 1
-    let mut scanner = Scanner::new(
-        sensors.clone(),
-        Range {
-            start: 0,
-            end: 4_000_000,
-        },
-        Range {
-            start: 0,
-            end: 4_000_000,
-        },
-    );
-    scanner.mark_non_beacon();
-
-    println!("Non-beacon: {}", scanner.count_non_beacon(2_000_000));
-
-    println!(
-        "Hidden beacon: {:?}",
-        scanner.hidden_beacon().tuning_frequency()
-    );
+    let scanner = Scanner::new(sensors, config.area(), config.area());
+
+    println!("Non-beacon: {}", scanner.count_non_beacon(config.row));
+
+    // Part 2
+    let hidden_beacon = scanner.hidden_beacon();
+    match &hidden_beacon {
+        Ok(point) => println!(
+            "Hidden beacon: {:?}",
+            point.tuning_frequency(config.multiplier)
+        ),
+        Err(err) => println!("Hidden beacon not found: {}", err),
+    }
+
+    if let Some(path) = &config.svg {
+        std::fs::write(
+            path,
+            render::to_svg(&scanner, hidden_beacon.as_ref().ok(), 800),
+        )
+        .unwrap_or_else(|err| panic!("Couldn't write {}: {}", path, err));
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::point::*;
     use super::*;
+    use std::ops::Range;
 
     #[test]
     fn calc_distance() {
@@ -63,7 +68,7 @@ mod tests {
     fn example_count() {
         let input = include_str!("../example");
         let sensors: Vec<Sensor> = input.lines().map(|it| it.into()).collect();
-        let mut scanner = Scanner::new(
+        let scanner = Scanner::new(
             sensors,
             Range {
                 start: i32::MIN,
@@ -71,29 +76,138 @@ mod tests {
             },
             Range { start: 10, end: 11 },
         );
-        scanner.mark_non_beacon();
 
         assert_eq!(scanner.count_non_beacon(10), 26);
     }
 
+    #[test]
+    fn example_row_coverage() {
+        let input = include_str!("../example");
+        let sensors: Vec<Sensor> = input.lines().map(|it| it.into()).collect();
+        let scanner = Scanner::new(sensors, 0..21, 0..21);
+
+        assert_eq!(scanner.row_coverage(10), vec![-2..25]);
+        assert_eq!(scanner.row_coverage(11), vec![-3..14, 15..26]);
+        assert!(scanner.row_coverage(-100).is_empty());
+
+        // Beacon at x=2, y=10 is known
+        assert!(scanner.is_covered(&Point::new(2, 10)));
+        assert!(!scanner.is_covered(&Point::new(14, 11)));
+    }
+
     #[test]
     fn example_hidden_beacon() {
         let input = include_str!("../example");
         let sensors: Vec<Sensor> = input.lines().map(|it| it.into()).collect();
-        let mut scanner = Scanner::new(
+        let scanner = Scanner::new(
             sensors,
-            Range {
-                start: i32::MIN,
-                end: 21,
-            },
-            Range {
-                start: i32::MIN,
-                end: 21,
-            },
+            Range { start: 0, end: 21 },
+            Range { start: 0, end: 21 },
         );
-        scanner.mark_non_beacon();
-        let hidden_beacon = scanner.hidden_beacon();
+        let hidden_beacon = scanner.hidden_beacon().unwrap();
+
+        assert_eq!(hidden_beacon.tuning_frequency(4_000_000), 56000011);
+    }
+
+    #[test]
+    fn hidden_beacon_errors() {
+        let input = include_str!("../example");
+        let sensors: Vec<Sensor> = input.lines().map(|it| it.into()).collect();
+
+        // Gap on the edge of a wider area
+        let scanner = Scanner::new(sensors.clone(), -1..21, 0..21);
+        assert!(!scanner.is_covered(&Point::new(-1, 8)));
+        assert_eq!(
+            scanner.hidden_beacon(),
+            Err(HiddenBeaconError::Ambiguous(vec![
+                Point::new(-1, 8),
+                Point::new(14, 11)
+            ]))
+        );
+
+        // Only covered points
+        let scanner = Scanner::new(sensors.clone(), 0..14, 0..21);
+        assert_eq!(scanner.hidden_beacon(), Err(HiddenBeaconError::NotFound));
+
+        // Gap at the edge of the area
+        let scanner = Scanner::new(sensors, 14..15, 11..12);
+        assert_eq!(scanner.hidden_beacon(), Ok(Point::new(14, 11)));
+
+        // Nothing covers the area
+        let scanner = Scanner::new(vec![], 0..1000, 0..1000);
+        assert_eq!(scanner.uncovered_points().count(), 1_000_000);
+        let err = scanner.hidden_beacon().unwrap_err();
+        match &err {
+            HiddenBeaconError::Ambiguous(points) => assert_eq!(points.len(), MAX_CANDIDATES + 1),
+            other => panic!("Unexpected {:?}", other),
+        }
+        assert!(err
+            .to_string()
+            .starts_with("more than 100 uncovered points"));
+    }
+
+    #[test]
+    fn uncovered_away_from_boundary_lines() {
+        // Uncovered points that aren't where two boundary lines cross
+        let sensors: Vec<Sensor> = [
+            (7, 4, 3),
+            (4, 11, 8),
+            (0, 0, 6),
+            (13, 0, 8),
+            (12, 3, 4),
+            (15, 13, 8),
+        ]
+        .into_iter()
+        .map(|(x, y, radius)| Sensor::new(Point::new(x, y), Point::new(x + radius, y)))
+        .collect();
+        let scanner = Scanner::new(sensors, 0..16, 0..16);
+        assert!(!scanner.is_covered(&Point::new(10, 7)));
+
+        let points: Vec<Point> = scanner.uncovered_points().collect();
+        assert_eq!(points.len(), 8);
+        assert!(points.contains(&Point::new(10, 7)));
+        assert!(points.iter().all(|it| !scanner.is_covered(it)));
+        assert_eq!(
+            scanner.hidden_beacon(),
+            Err(HiddenBeaconError::Ambiguous(points))
+        );
+    }
+
+    #[test]
+    fn example_svg() {
+        let input = include_str!("../example");
+        let sensors: Vec<Sensor> = input.lines().map(|it| it.into()).collect();
+        let scanner = Scanner::new(sensors, 0..21, 0..21);
+        let svg = render::to_svg(&scanner, Some(&Point::new(14, 11)), 270);
+
+        assert!(svg.starts_with(r#"<svg xmlns="http://www.w3.org/2000/svg" width="270" height="220" viewBox="-2 0 27 22">"#));
+        assert_eq!(svg.matches("<polygon").count(), 14);
+        assert_eq!(svg.matches("<circle").count(), 29);
+        assert!(svg.contains(r#"<polygon points="8,-2 17,7 8,16 -1,7""#));
+        assert!(svg.ends_with("</svg>"));
+    }
+
+    #[test]
+    fn config_file() {
+        let mut config = Config::default();
+        config.apply_file(include_str!("../example.config"));
+
+        assert_eq!(config.row, 10);
+        assert_eq!(config.area(), 0..21);
+        assert_eq!(config.multiplier, 4_000_000);
+    }
+
+    #[test]
+    fn config_args() {
+        let args = ["--input", "example", "--row", "11", "--multiplier", "10"];
+        let config = Config::from_args(args.map(String::from).into_iter());
+
+        // Command line wins over example.config
+        assert_eq!(config.input, Some("example".to_string()));
+        assert_eq!(config.row, 11);
+        assert_eq!(config.max, 20);
+        assert_eq!(config.multiplier, 10);
 
-        assert_eq!(hidden_beacon.tuning_frequency(), 56000011);
+        assert_eq!(Config::from_args(std::iter::empty()), Config::default());
     }
 }
@@ -17,7 +17,7 @@ impl Point {
         self.0.abs_diff(b.0) + self.1.abs_diff(b.1)
     }
 
-    pub fn tuning_frequency(&self) -> i64 {
-        self.0 as i64 * 4000000 + self.1 as i64
+    pub fn tuning_frequency(&self, multiplier: i64) -> i64 {
+        self.0 as i64 * multiplier + self.1 as i64
     }
 }
@@ -0,0 +1,75 @@
+use crate::point::*;
+use crate::scanner::*;
+use crate::sensor::*;
+
+/// Draws the sensors, their coverage, the beacons and the hidden beacon as an SVG image
+/// over the bounding box of sensors and beacons, `width` pixels wide.
+pub fn to_svg(scanner: &Scanner, hidden_beacon: Option<&Point>, width: u32) -> String {
+    let (min, max) = scanner.sensors.min_max();
+    let box_width = (max.x() - min.x()).max(1) as f64;
+    let box_height = (max.y() - min.y()).max(1) as f64;
+    let height = (width as f64 * box_height / box_width).ceil() as u32;
+
+    // Markers keep the same size on screen whatever the scale is
+    let marker = box_width / width as f64 * 4.0;
+
+    let mut svg = vec![
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" viewBox="{} {} {} {}">"#,
+            width,
+            height,
+            min.x(),
+            min.y(),
+            box_width,
+            box_height
+        ),
+        format!(
+            r##"<rect x="{}" y="{}" width="{}" height="{}" fill="#0f0f23"/>"##,
+            min.x(),
+            min.y(),
+            box_width,
+            box_height
+        ),
+    ];
+
+    // Coverage diamonds
+    for sensor in scanner.sensors.iter() {
+        let (x, y) = (sensor.position.x(), sensor.position.y());
+        let r = sensor.radius() as i32;
+
+        svg.push(format!(
+            r##"<polygon points="{},{} {},{} {},{} {},{}" fill="#00cc00" fill-opacity="0.15" stroke="#00cc00" stroke-width="1" vector-effect="non-scaling-stroke"/>"##,
+            x,
+            y - r,
+            x + r,
+            y,
+            x,
+            y + r,
+            x - r,
+            y
+        ));
+    }
+
+    // Sensors and their closest beacons
+    for sensor in scanner.sensors.iter() {
+        svg.push(circle(&sensor.position, marker, "#ffff66"));
+        svg.push(circle(&sensor.closest_beacon, marker, "#6699ff"));
+    }
+
+    if let Some(point) = hidden_beacon {
+        svg.push(circle(point, marker * 2.0, "#ff3333"));
+    }
+
+    svg.push("</svg>".to_string());
+    svg.join("\n")
+}
+
+fn circle(point: &Point, radius: f64, color: &str) -> String {
+    format!(
+        r#"<circle cx="{}" cy="{}" r="{}" fill="{}"/>"#,
+        point.x(),
+        point.y(),
+        radius,
+        color
+    )
+}
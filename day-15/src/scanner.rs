@@ -1,15 +1,44 @@
-use std::collections::HashMap;
-use std::collections::VecDeque;
+use std::collections::HashSet;
 use std::ops::Range;
 
 use crate::point::*;
 use crate::sensor::*;
 
+/// How many uncovered points are reported when there are too many
+pub const MAX_CANDIDATES: usize = 100;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum HiddenBeaconError {
+    /// Every point of the search area is covered
+    NotFound,
+    /// More than one point is uncovered, the first `MAX_CANDIDATES` + 1 are listed, so
+    /// a full list means there may be many more
+    Ambiguous(Vec<Point>),
+}
+
+impl std::fmt::Display for HiddenBeaconError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HiddenBeaconError::NotFound => write!(f, "every point is covered by a sensor"),
+            HiddenBeaconError::Ambiguous(points) if points.len() > MAX_CANDIDATES => {
+                write!(
+                    f,
+                    "more than {} uncovered points, starting with {:?}",
+                    MAX_CANDIDATES,
+                    &points[..MAX_CANDIDATES]
+                )
+            }
+            HiddenBeaconError::Ambiguous(points) => {
+                write!(f, "{} uncovered points: {:?}", points.len(), points)
+            }
+        }
+    }
+}
+
 pub struct Scanner {
     pub sensors: Vec<Sensor>,
     pub range_x: Range<i32>,
     pub range_y: Range<i32>,
-    pub ranges: HashMap<i32, Vec<Range<i32>>>,
 }
 
 impl Scanner {
@@ -18,94 +47,100 @@ impl Scanner {
             sensors,
             range_x,
             range_y,
-            ranges: HashMap::new(),
         }
     }
 
-    #[inline]
-    pub fn make_pyramid(&mut self, point: &Point, distance: i32, increase: i32) {
-        let mut y = point.y();
-        let x = point.x();
-
-        // Check for range x
-        if !self.range_x.contains(&x) {
-            return;
-        }
+    /// Sorted and merged ranges of `x` covered by the sensors on row `y`
+    pub fn row_coverage(&self, y: i32) -> Vec<Range<i32>> {
+        let mut ranges: Vec<Range<i32>> = self
+            .sensors
+            .iter()
+            .filter_map(|sensor| {
+                let remaining = sensor.radius() as i64 - sensor.position.y().abs_diff(y) as i64;
+                if remaining < 0 {
+                    return None;
+                }
 
-        for distance in (0..=distance).rev() {
-            // Check for range y
-            if !self.range_y.contains(&y) {
-                y += increase;
+                let x = sensor.position.x();
+                let remaining = remaining as i32;
 
-                continue;
-            }
+                Some((x - remaining)..(x + remaining + 1))
+            })
+            .collect();
 
-            let row = self.ranges.entry(y).or_insert(vec![]);
-            row.push((x - distance)..(x + distance));
+        ranges.sort_unstable_by_key(|it| it.start);
 
-            y += increase;
+        let mut merged: Vec<Range<i32>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match merged.last_mut() {
+                // Overlapping or touching
+                Some(prev) if range.start <= prev.end => prev.end = prev.end.max(range.end),
+                _ => merged.push(range),
+            }
         }
-    }
 
-    pub fn mark_non_beacon(&mut self) {
-        let sensors: Vec<Sensor> = self.sensors.clone();
-
-        for sensor in sensors {
-            // Calculate distance
-            let beacon_distance = sensor.position.manhattan_distance(&sensor.closest_beacon) as i32;
+        merged
+    }
 
-            // Going up
-            self.make_pyramid(&sensor.position, beacon_distance, -1);
+    #[cfg(test)]
+    pub fn is_covered(&self, point: &Point) -> bool {
+        self.sensors
+            .iter()
+            .any(|sensor| sensor.position.manhattan_distance(point) <= sensor.radius())
+    }
 
-            // Going down
-            self.make_pyramid(&sensor.position, beacon_distance, 1);
-        }
+    /// Every point of the search area not covered by any sensor, row by row.
+    ///
+    /// Gaps between the merged ranges of each row are uncovered, as well as the ends
+    /// of the row not reached by any range.
+    pub fn uncovered_points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.range_y.clone().flat_map(move |y| {
+            let mut gaps: Vec<Range<i32>> = vec![];
+            let mut x = self.range_x.start;
+
+            for range in self.row_coverage(y) {
+                if range.start > x {
+                    gaps.push(x..range.start.min(self.range_x.end));
+                }
 
-        // Sort and merge all ranges
-        for (_, ranges) in self.ranges.iter_mut() {
-            ranges.sort_unstable_by(|a, b| a.start.cmp(&b.start));
-
-            let mut merged: VecDeque<Range<i32>> = VecDeque::new();
-            merged.push_back(ranges.get(0).unwrap().clone());
-
-            for range in ranges.iter().skip(1) {
-                let prev = merged.back().unwrap();
-                if range.start <= prev.end {
-                    // Overlap
-                    let new = Range {
-                        start: prev.start,
-                        end: prev.end.max(range.end),
-                    };
-
-                    merged.pop_back();
-                    merged.push_back(new);
-                } else {
-                    merged.push_back(range.clone());
+                x = x.max(range.end);
+                if x >= self.range_x.end {
+                    break;
                 }
             }
 
-            *ranges = Vec::from(merged);
-        }
-    }
+            if x < self.range_x.end {
+                gaps.push(x..self.range_x.end);
+            }
 
-    pub fn hidden_beacon(&self) -> Point {
-        let multiple: HashMap<&i32, &Vec<Range<i32>>> = self
-            .ranges
-            .iter()
-            .filter(|(_, ranges)| ranges.len() == 2 && ranges[1].start == ranges[0].end + 2)
-            .collect();
+            gaps.into_iter().flatten().map(move |x| Point::new(x, y))
+        })
+    }
 
-        let (y, ranges) = multiple.into_iter().last().unwrap();
+    /// Finds the only point in the search area not covered by any sensor, stopping
+    /// once more than `MAX_CANDIDATES` are found
+    pub fn hidden_beacon(&self) -> Result<Point, HiddenBeaconError> {
+        let mut candidates: Vec<Point> = self.uncovered_points().take(MAX_CANDIDATES + 1).collect();
 
-        Point::new(ranges[0].end + 1, *y)
+        match candidates.len() {
+            0 => Err(HiddenBeaconError::NotFound),
+            1 => Ok(candidates.remove(0)),
+            _ => Err(HiddenBeaconError::Ambiguous(candidates)),
+        }
     }
 
+    /// Positions on row `y` where a beacon can't be, known beacons excluded
     pub fn count_non_beacon(&self, y: i32) -> usize {
-        self.ranges
-            .get(&y)
-            .unwrap()
+        let coverage = self.row_coverage(y);
+        let covered: usize = coverage.iter().map(|it| it.len()).sum();
+
+        let beacons: HashSet<&Point> = self
+            .sensors
             .iter()
-            .map(|it| it.clone().count())
-            .sum()
+            .map(|it| &it.closest_beacon)
+            .filter(|it| it.y() == y && coverage.iter().any(|range| range.contains(&it.x())))
+            .collect();
+
+        covered - beacons.len()
     }
 }
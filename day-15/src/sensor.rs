@@ -17,6 +17,11 @@ impl Sensor {
             closest_beacon,
         }
     }
+
+    /// Distance to the closest beacon, no other beacon is within it
+    pub fn radius(&self) -> u32 {
+        self.position.manhattan_distance(&self.closest_beacon)
+    }
 }
 
 impl From<&str> for Sensor {
@@ -1,15 +1,16 @@
 #[macro_use]
 extern crate lazy_static;
 
-use std::collections::HashMap;
+mod solver;
 
 use regex::Regex;
+use solver::*;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-struct Valve {
-    name: String,
-    flow_rate: i32,
-    links: Vec<String>,
+pub struct Valve {
+    pub name: String,
+    pub flow_rate: i32,
+    pub links: Vec<String>,
 }
 
 impl Valve {}
@@ -39,67 +40,70 @@ impl From<&str> for Valve {
     }
 }
 
-fn main() {
-    let input = include_str!("../example");
-    let mut valves: Vec<Valve> = input.lines().map(|it| Valve::from(it)).collect();
-    let max_time = 30;
-
-    valves.sort_unstable_by(|a, b| b.flow_rate.cmp(&a.flow_rate));
-
-    let valves_idx: HashMap<&String, usize> = valves
-        .iter()
-        .enumerate()
-        .map(|(i, v)| (&v.name, i))
-        .collect();
-
-    let good_valves = valves.iter().filter(|valve| valve.flow_rate > 0).count();
-    let total_valves = valves.len();
-
-    let mut adj = vec![vec![0usize; 0]; total_valves];
-    let mut flow = vec![0i32; total_valves];
+struct Options {
+    start: String,
+    actors: Option<usize>,
+    minutes: Option<usize>,
+}
 
-    for valve in valves.iter() {
-        let i = valves_idx[&valve.name];
-        flow[i] = valve.flow_rate;
-        for w in valve.links.iter() {
-            adj[i].push(valves_idx[w]);
+impl Options {
+    /// Reads `--start <valve>`, `--actors <n>` and `--minutes <n>` from the command line
+    fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self {
+            start: "AA".to_string(),
+            actors: None,
+            minutes: None,
+        };
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .unwrap_or_else(|| panic!("Missing value after {}", arg));
+
+            match arg.as_str() {
+                "--start" => options.start = value,
+                "--actors" => options.actors = Some(value.parse().expect("Invalid actors")),
+                "--minutes" => options.minutes = Some(value.parse().expect("Invalid minutes")),
+                _ => panic!("Unknown argument: {}", arg),
+            }
         }
-    }
-
-    let aa = valves_idx[&"AA".to_string()];
 
-    let mm = 1 << good_valves;
-    let mut values = vec![vec![vec![0; mm]; total_valves]; max_time];
+        options
+    }
+}
 
-    for t in 1..max_time {
-        for i in 0..total_valves {
-            let ii = 1 << i;
-            for x in 0..mm {
-                let mut current = values[t][i][x];
-                if ii & x != 0 && t >= 2 {
-                    current = current.max(values[t - 1][i][x - ii] + flow[i] * t as i32);
-                }
+fn main() {
+    let input = include_str!("../input");
+    let valves: Vec<Valve> = input.lines().map(Valve::from).collect();
+    let options = Options::from_args(std::env::args().skip(1));
+    let solver = Solver::new(&valves, &options.start);
+
+    if options.actors.is_some() || options.minutes.is_some() {
+        let actors = options.actors.unwrap_or(1);
+        let minutes = options.minutes.unwrap_or(30);
+        let schedules = solver.best_team(actors, minutes);
+
+        for (i, schedule) in schedules.iter().enumerate() {
+            println!("Actor {}:\n{}", i + 1, schedule);
+        }
 
-                for &j in adj[i].iter() {
-                    current = current.max(values[t - 1][j][x]);
-                }
+        let total: i32 = schedules.iter().map(|it| it.pressure).sum();
+        println!("{} actors, {} minutes: {}", actors, minutes, total);
 
-                values[t][i][x] = current;
-            }
-        }
+        return;
     }
 
     // Part 1
-    println!("Part 1: {}", values[29][aa][mm - 1]);
+    let schedule = solver.best(30);
+    println!("{}", schedule);
+    println!("Part 1: {}", schedule.pressure);
 
     // Part 2
-    let mut best = 0;
-    for x in 0..mm / 2 {
-        let y = mm - 1 - x;
-        best = best.max(values[25][aa][x] + values[25][aa][y]);
-    }
-
-    println!("Part 2: {}", best);
+    let team = solver.best_team(2, 26);
+    println!("Human:\n{}", team[0]);
+    println!("Elephant:\n{}", team[1]);
+    println!("Part 2: {}", team[0].pressure + team[1].pressure);
 }
 
 #[cfg(test)]
@@ -118,4 +122,125 @@ mod tests {
             }
         )
     }
+
+    fn opened(schedule: &Schedule) -> Vec<(usize, &str)> {
+        schedule
+            .actions
+            .iter()
+            .filter_map(|(minute, action)| match action {
+                Action::Open(valve) => Some((*minute, valve.as_str())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Pressure released by the valves opened on a schedule
+    fn released(valves: &[Valve], schedule: &Schedule, minutes: usize) -> i32 {
+        opened(schedule)
+            .iter()
+            .map(|(minute, name)| {
+                let valve = valves.iter().find(|it| it.name == *name).unwrap();
+                valve.flow_rate * (minutes - minute) as i32
+            })
+            .sum()
+    }
+
+    #[test]
+    fn example_schedule() {
+        let input = include_str!("../example");
+        let valves: Vec<Valve> = input.lines().map(Valve::from).collect();
+        let solver = Solver::new(&valves, "AA");
+        let schedule = solver.best(30);
+
+        assert_eq!(schedule.pressure, 1651);
+        assert_eq!(released(&valves, &schedule, 30), 1651);
+        assert_eq!(
+            opened(&schedule),
+            vec![
+                (2, "DD"),
+                (5, "BB"),
+                (9, "JJ"),
+                (17, "HH"),
+                (21, "EE"),
+                (24, "CC")
+            ]
+        );
+        assert_eq!(schedule.actions[0], (1, Action::MoveTo("DD".to_string())));
+    }
+
+    #[test]
+    fn bigger_teams() {
+        let input = include_str!("../example");
+        let valves: Vec<Valve> = input.lines().map(Valve::from).collect();
+        let solver = Solver::new(&valves, "AA");
+        let total = |actors, minutes| -> i32 {
+            let team = solver.best_team(actors, minutes);
+            assert_eq!(team.len(), actors);
+
+            team.iter().map(|it| it.pressure).sum()
+        };
+
+        assert_eq!(total(1, 30), 1651);
+        assert_eq!(total(2, 26), 1707);
+
+        // More help or more time never hurts
+        assert!(total(3, 26) >= 1707);
+        assert!(total(1, 40) > 1651);
+        assert_eq!(total(3, 0), 0);
+        assert_eq!(total(0, 30), 0);
+    }
+
+    #[test]
+    fn other_start() {
+        let input = include_str!("../example");
+        let valves: Vec<Valve> = input.lines().map(Valve::from).collect();
+        let solver = Solver::new(&valves, "HH");
+        let schedule = solver.best(2);
+
+        assert_eq!(schedule.pressure, 22);
+        assert_eq!(schedule.actions, vec![(1, Action::Open("HH".to_string()))]);
+    }
+
+    #[test]
+    fn options_args() {
+        let args = ["--start", "BB", "--actors", "3", "--minutes", "40"];
+        let options = Options::from_args(args.map(String::from).into_iter());
+
+        assert_eq!(options.start, "BB");
+        assert_eq!(options.actors, Some(3));
+        assert_eq!(options.minutes, Some(40));
+    }
+
+    #[test]
+    fn pruning_keeps_best() {
+        let input = include_str!("../input");
+        let valves: Vec<Valve> = input.lines().map(Valve::from).collect();
+        let solver = Solver::new(&valves, "AA");
+
+        for minutes in [10, 20, 30] {
+            assert_eq!(solver.best(minutes).pressure, solver.best_unpruned(minutes));
+        }
+    }
+
+    #[test]
+    fn example_pair_schedule() {
+        let input = include_str!("../example");
+        let valves: Vec<Valve> = input.lines().map(Valve::from).collect();
+        let solver = Solver::new(&valves, "AA");
+        let team = solver.best_team(2, 26);
+        let (human, elephant) = (&team[0], &team[1]);
+
+        assert_eq!(human.pressure + elephant.pressure, 1707);
+        assert_eq!(released(&valves, human, 26), human.pressure);
+        assert_eq!(released(&valves, elephant, 26), elephant.pressure);
+
+        // Nobody opens the same valve twice
+        let mut all: Vec<&str> = opened(human)
+            .into_iter()
+            .chain(opened(elephant))
+            .map(|(_, valve)| valve)
+            .collect();
+        all.sort_unstable();
+        assert_eq!(all, vec!["BB", "CC", "DD", "EE", "HH", "JJ"]);
+    }
 }
@@ -0,0 +1,325 @@
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Display;
+
+use crate::Valve;
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Action {
+    MoveTo(String),
+    Open(String),
+}
+
+/// What an actor does on each minute, and how much pressure it releases
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Schedule {
+    pub pressure: i32,
+    pub actions: Vec<(usize, Action)>,
+}
+
+impl Display for Schedule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (minute, action) in self.actions.iter() {
+            match action {
+                Action::MoveTo(valve) => {
+                    writeln!(f, "== Minute {} == move to valve {}", minute, valve)?
+                }
+                Action::Open(valve) => writeln!(f, "== Minute {} == open valve {}", minute, valve)?,
+            }
+        }
+
+        write!(f, "Total pressure released: {}", self.pressure)
+    }
+}
+
+/// Best pressure found while opening a set of valves, and the order they were opened in
+#[derive(Debug, Clone, Default)]
+struct Route {
+    pressure: i32,
+    order: Vec<usize>,
+}
+
+/// Searches over the tunnels compressed to the valves with flow.
+///
+/// Corridors with broken valves are only ever walked through, so the search jumps
+/// straight between useful valves using their shortest distance.
+pub struct Solver {
+    valves: Vec<Valve>,
+    adj: Vec<Vec<usize>>,
+    /// Valve index of each node of the compressed graph
+    nodes: Vec<usize>,
+    flow: Vec<i32>,
+    /// `dist[a][b]`: minutes to walk from node `a` to node `b`, the start is the last node
+    dist: Vec<Vec<usize>>,
+}
+
+impl Solver {
+    pub fn new(valves: &[Valve], start: &str) -> Self {
+        let valves = valves.to_vec();
+        let valves_idx: HashMap<&String, usize> = valves
+            .iter()
+            .enumerate()
+            .map(|(i, v)| (&v.name, i))
+            .collect();
+
+        let adj: Vec<Vec<usize>> = valves
+            .iter()
+            .map(|valve| valve.links.iter().map(|w| valves_idx[w]).collect())
+            .collect();
+
+        let mut nodes: Vec<usize> = (0..valves.len())
+            .filter(|i| valves[*i].flow_rate > 0)
+            .collect();
+        let flow = nodes.iter().map(|i| valves[*i].flow_rate).collect();
+        nodes.push(valves_idx[&start.to_string()]);
+
+        let dist = nodes
+            .iter()
+            .map(|from| {
+                let all = distances(&adj, *from);
+                nodes.iter().map(|to| all[*to]).collect()
+            })
+            .collect();
+
+        Self {
+            valves,
+            adj,
+            nodes,
+            flow,
+            dist,
+        }
+    }
+
+    fn start(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    /// Pressure still possible if every closed valve was opened right after walking to it
+    fn upper_bound(&self, at: usize, time: usize, opened: usize) -> i32 {
+        (0..self.flow.len())
+            .filter(|k| opened & (1 << k) == 0)
+            .map(|k| self.flow[k] * time.saturating_sub(self.dist[at][k] + 1) as i32)
+            .sum()
+    }
+
+    fn search(&self, at: usize, time: usize, opened: usize, current: &mut Route, best: &mut Route) {
+        if current.pressure > best.pressure {
+            *best = current.clone();
+        }
+
+        if current.pressure + self.upper_bound(at, time, opened) <= best.pressure {
+            return;
+        }
+
+        for k in 0..self.flow.len() {
+            let cost = self.dist[at][k] + 1;
+            if opened & (1 << k) != 0 || cost >= time {
+                continue;
+            }
+
+            let released = self.flow[k] * (time - cost) as i32;
+            current.pressure += released;
+            current.order.push(k);
+
+            self.search(k, time - cost, opened | (1 << k), current, best);
+
+            current.pressure -= released;
+            current.order.pop();
+        }
+    }
+
+    /// Best route for every set of opened valves, without pruning
+    fn search_all(
+        &self,
+        at: usize,
+        time: usize,
+        opened: usize,
+        current: &mut Route,
+        routes: &mut HashMap<usize, Route>,
+    ) {
+        let entry = routes.entry(opened).or_default();
+        if current.pressure > entry.pressure || opened == 0 {
+            *entry = current.clone();
+        }
+
+        for k in 0..self.flow.len() {
+            let cost = self.dist[at][k] + 1;
+            if opened & (1 << k) != 0 || cost >= time {
+                continue;
+            }
+
+            let released = self.flow[k] * (time - cost) as i32;
+            current.pressure += released;
+            current.order.push(k);
+
+            self.search_all(k, time - cost, opened | (1 << k), current, routes);
+
+            current.pressure -= released;
+            current.order.pop();
+        }
+    }
+
+    /// Best schedule for a single actor within `minutes`
+    pub fn best(&self, minutes: usize) -> Schedule {
+        let mut best = Route::default();
+        self.search(self.start(), minutes, 0, &mut Route::default(), &mut best);
+
+        self.schedule(&best)
+    }
+
+    /// Best schedules for `actors` working together within `minutes`, each opening
+    /// different valves.
+    ///
+    /// Valves are split between actors by trying every partition of the useful ones,
+    /// which takes `3^n` steps per actor for `n` valves with flow. Without actors the
+    /// team is empty and releases no pressure.
+    pub fn best_team(&self, actors: usize, minutes: usize) -> Vec<Schedule> {
+        match actors {
+            0 => return vec![],
+            1 => return vec![self.best(minutes)],
+            _ => {}
+        }
+
+        let mut routes: HashMap<usize, Route> = HashMap::new();
+        self.search_all(self.start(), minutes, 0, &mut Route::default(), &mut routes);
+
+        // `within[s]`: set of opened valves with the best route among subsets of `s`
+        let mm = 1 << self.flow.len();
+        let mut within: Vec<usize> = (0..mm)
+            .map(|s| if routes.contains_key(&s) { s } else { 0 })
+            .collect();
+        let pressure = |s: usize| routes.get(&s).map_or(0, |it| it.pressure);
+
+        for bit in 0..self.flow.len() {
+            for s in 0..mm {
+                if s & (1 << bit) != 0 && pressure(within[s ^ (1 << bit)]) > pressure(within[s]) {
+                    within[s] = within[s ^ (1 << bit)];
+                }
+            }
+        }
+
+        // `best[k][s]`: best pressure for `k + 1` actors sharing the valves in `s`,
+        // and the valves given to one of them
+        let mut best: Vec<Vec<(i32, usize)>> =
+            vec![(0..mm).map(|s| (pressure(within[s]), s)).collect()];
+
+        for k in 1..actors {
+            let row = (0..mm)
+                .map(|s| {
+                    let mut choice = (best[k - 1][s].0, 0);
+                    let mut t = s;
+
+                    // Every non-empty subset of `s`
+                    while t > 0 {
+                        let value = pressure(within[t]) + best[k - 1][s ^ t].0;
+                        if value > choice.0 {
+                            choice = (value, t);
+                        }
+
+                        t = (t - 1) & s;
+                    }
+
+                    choice
+                })
+                .collect();
+
+            best.push(row);
+        }
+
+        let mut schedules = vec![];
+        let mut s = mm - 1;
+
+        for k in (1..actors).rev() {
+            let t = best[k][s].1;
+            schedules.push(self.schedule(&routes[&within[t]]));
+            s ^= t;
+        }
+        schedules.push(self.schedule(&routes[&within[s]]));
+
+        schedules
+    }
+
+    /// Best pressure over every set of opened valves, found without pruning
+    #[cfg(test)]
+    pub fn best_unpruned(&self, minutes: usize) -> i32 {
+        let mut routes: HashMap<usize, Route> = HashMap::new();
+        self.search_all(self.start(), minutes, 0, &mut Route::default(), &mut routes);
+
+        routes.values().map(|it| it.pressure).max().unwrap_or(0)
+    }
+
+    /// Walks between the valves of a route and opens them
+    fn schedule(&self, route: &Route) -> Schedule {
+        let mut actions = vec![];
+        let mut minute = 0;
+        let mut at = self.nodes[self.start()];
+
+        for k in route.order.iter() {
+            let target = self.nodes[*k];
+
+            for valve in path(&self.adj, at, target) {
+                minute += 1;
+                actions.push((minute, Action::MoveTo(self.valves[valve].name.clone())));
+            }
+
+            minute += 1;
+            actions.push((minute, Action::Open(self.valves[target].name.clone())));
+            at = target;
+        }
+
+        Schedule {
+            pressure: route.pressure,
+            actions,
+        }
+    }
+}
+
+/// Breadth-first search from `from`, returning the parent of every reached valve
+fn parents(adj: &[Vec<usize>], from: usize) -> Vec<Option<usize>> {
+    let mut parents = vec![None; adj.len()];
+    let mut queue = VecDeque::from([from]);
+    parents[from] = Some(from);
+
+    while let Some(current) = queue.pop_front() {
+        for &next in adj[current].iter() {
+            if parents[next].is_none() {
+                parents[next] = Some(current);
+                queue.push_back(next);
+            }
+        }
+    }
+
+    parents
+}
+
+/// Minutes to walk from `from` to every valve, unreachable ones are too far to matter
+fn distances(adj: &[Vec<usize>], from: usize) -> Vec<usize> {
+    let mut dist = vec![usize::MAX / 2; adj.len()];
+    let mut queue = VecDeque::from([from]);
+    dist[from] = 0;
+
+    while let Some(current) = queue.pop_front() {
+        for &next in adj[current].iter() {
+            if dist[next] > dist[current] + 1 {
+                dist[next] = dist[current] + 1;
+                queue.push_back(next);
+            }
+        }
+    }
+
+    dist
+}
+
+/// Valves walked through going from `from` to `to`, excluding `from`
+fn path(adj: &[Vec<usize>], from: usize, to: usize) -> Vec<usize> {
+    let parents = parents(adj, from);
+    let mut path = vec![];
+    let mut current = to;
+
+    while current != from {
+        path.push(current);
+        current = parents[current].expect("Unreachable valve");
+    }
+
+    path.reverse();
+    path
+}
@@ -1,5 +1,14 @@
+mod render;
+mod shape;
+
 use std::collections::HashMap;
-use std::ops::{Add, Range};
+use std::ops::Add;
+
+use render::*;
+use shape::*;
+
+/// Columns deeper than this below the top are considered the same in the surface profile
+const PROFILE_DEPTH: i32 = 64;
 
 // Point movements
 const DOWN: Point = Point(0, -1);
@@ -7,7 +16,7 @@ const LEFT: Point = Point(-1, 0);
 const RIGHT: Point = Point(1, 0);
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
-struct Point(i32, i32);
+pub struct Point(i32, i32);
 
 impl Point {
     fn new(x: i32, y: i32) -> Self {
@@ -38,123 +47,62 @@ impl From<char> for Point {
     }
 }
 
-#[derive(Debug)]
-enum RockShape {
-    A,
-    B,
-    C,
-    D,
-    E,
+/// Next shape, next jet and how far below the top each column is
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct State {
+    shape: usize,
+    jet: usize,
+    profile: Vec<i32>,
 }
 
-impl RockShape {
-    fn next_shape(&self) -> RockShape {
-        match self {
-            RockShape::A => RockShape::B,
-            RockShape::B => RockShape::C,
-            RockShape::C => RockShape::D,
-            RockShape::D => RockShape::E,
-            RockShape::E => RockShape::A,
-        }
-    }
-    fn to_point(&self) -> Vec<Point> {
-        match self {
-            RockShape::A => {
-                vec![
-                    Point::new(0, 0),
-                    Point::new(1, 0),
-                    Point::new(2, 0),
-                    Point::new(3, 0),
-                ]
-            }
-            RockShape::B => {
-                vec![
-                    Point::new(1, 0),
-                    Point::new(0, 1),
-                    Point::new(1, 1),
-                    Point::new(2, 1),
-                    Point::new(1, 2),
-                ]
-            }
-            RockShape::C => {
-                vec![
-                    Point::new(0, 0),
-                    Point::new(1, 0),
-                    Point::new(2, 0),
-                    Point::new(2, 1),
-                    Point::new(2, 2),
-                ]
-            }
-            RockShape::D => {
-                vec![
-                    Point::new(0, 0),
-                    Point::new(0, 1),
-                    Point::new(0, 2),
-                    Point::new(0, 3),
-                ]
-            }
-            RockShape::E => {
-                vec![
-                    Point::new(0, 0),
-                    Point::new(1, 0),
-                    Point::new(0, 1),
-                    Point::new(1, 1),
-                ]
-            }
-        }
-    }
-
-    fn relative_to(&self, point: Point) -> Vec<Point> {
-        let points = self
-            .to_point()
-            .iter()
-            .map(|it| {
-                let mut new = it.clone();
-                new.apply(&point);
-
-                new
-            })
-            .collect();
-
-        points
-    }
+/// Rocks repeat the same `height` gain every `length` rocks once `start` rocks fell
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cycle {
+    start: usize,
+    length: usize,
+    height: i64,
 }
 
+/// Chamber where rocks fall, stored as one bitmask per row with bit `x` for column `x`
 struct Cave {
-    map: HashMap<Point, char>,
+    rules: Rules,
+    /// Bitmask rows of each shape
+    shapes: Vec<Vec<u32>>,
+    /// Rows above the floor, the first one is at height `base + 1`
+    rows: Vec<u32>,
+    /// Rows dropped below a fully blocked one, rocks can't get there anymore
+    base: i32,
     highest_y: i32,
     jet_movements: Vec<Point>,
     curr_jet_movement: usize,
-    current_shape: RockShape,
-    x_boundaries: Range<i32>,
-    curr_n: i32,
+    current_shape: usize,
     deltas: Vec<i32>,
 }
 
 impl Cave {
-    fn new(jet_movements: Vec<Point>) -> Self {
+    fn new(jet_movements: Vec<Point>, rules: Rules) -> Self {
+        if let Err(err) = rules.validate() {
+            panic!("Invalid rules: {}", err);
+        }
+
         Self {
-            map: HashMap::new(),
-            current_shape: RockShape::A,
+            shapes: rules.shapes.iter().map(|it| it.rows()).collect(),
+            rules,
+            rows: vec![],
+            base: 0,
+            current_shape: 0,
             highest_y: 0,
-            x_boundaries: 0..7,
             jet_movements,
             curr_jet_movement: 0,
-            curr_n: 0,
             deltas: vec![],
         }
     }
 
-    fn get_char(&mut self) -> char {
-        let ch = self.curr_n.to_string().chars().last().unwrap();
-
-        self.curr_n = (self.curr_n + 1) % 10;
-
-        ch
-    }
-
     fn spawn_position(&self) -> Point {
-        Point::new(2, self.highest_y + 4)
+        Point::new(
+            self.rules.spawn_x,
+            self.highest_y + self.rules.spawn_gap + 1,
+        )
     }
 
     fn get_jet_movement(&mut self) -> Point {
@@ -167,47 +115,58 @@ impl Cave {
         mov.clone()
     }
 
-    fn can_move(&self, point: &Point, movement: &Point) -> bool {
-        let mut tmp = point.clone();
-        tmp.apply(movement);
-
-        // Floor
-        if tmp.1 <= 0 {
-            return false;
+    /// Blocked cells of the row at height `y`
+    fn row(&self, y: i32) -> u32 {
+        match y - self.base - 1 {
+            i if i < 0 => self.rules.full_row(),
+            i => self.rows.get(i as usize).copied().unwrap_or(0),
         }
+    }
 
-        // X boundaries
-        if !self.x_boundaries.contains(&tmp.0) {
+    /// Whether `shape` fits with its bottom left corner at `position`
+    fn fits(&self, shape: &[u32], position: &Point) -> bool {
+        // Floor and x boundaries
+        if position.1 <= 0 || position.0 < 0 || position.0 >= self.rules.width {
             return false;
         }
 
-        self.map.get(&tmp).is_none()
+        shape.iter().enumerate().all(|(i, row)| {
+            let row = row << position.0;
+
+            row <= self.rules.full_row() && row & self.row(position.1 + i as i32) == 0
+        })
     }
 
     fn tick(&mut self) {
+        self.tick_with(|_, _, _| {});
+    }
+
+    /// Drops the next rock, calling `on_step` with the rock's rows and position
+    /// after it spawns and after every push and fall
+    fn tick_with(&mut self, mut on_step: impl FnMut(&Cave, Step, (&[u32], &Point))) {
         // Spawn shape
-        let spawn_pos = self.spawn_position();
-        let mut shape = self.current_shape.relative_to(spawn_pos.clone());
+        let shape = self.shapes[self.current_shape].clone();
+        let mut position = self.spawn_position();
+        on_step(self, Step::Spawn, (&shape, &position));
 
         // Move the shape down until it's stable
         loop {
             // Apply jet movements
             let jet_mov = self.get_jet_movement();
-
-            // Check if can move
-            if shape.iter().all(|p| self.can_move(p, &jet_mov)) {
-                // Apply movement
-                shape.iter_mut().for_each(|p| {
-                    p.apply(&jet_mov);
-                });
+            let pushed = self.fits(&shape, &(position.clone() + jet_mov.clone()));
+            if pushed {
+                position.apply(&jet_mov);
             }
+            on_step(self, Step::Push(jet_mov, pushed), (&shape, &position));
+
             // Move down
-            if shape.iter().all(|p| self.can_move(p, &DOWN)) {
-                // Apply down movement
-                shape.iter_mut().for_each(|p| {
-                    p.apply(&DOWN);
-                });
-            } else {
+            let fell = self.fits(&shape, &(position.clone() + DOWN));
+            if fell {
+                position.apply(&DOWN);
+            }
+            on_step(self, Step::Fall(fell), (&shape, &position));
+
+            if !fell {
                 break;
             }
         }
@@ -215,24 +174,152 @@ impl Cave {
         let prev_highest = self.highest_y;
 
         // Write blocks and get highest_y
-        let ch = self.get_char();
-        shape.iter().for_each(|p| {
-            self.highest_y = self.highest_y.max(p.1);
+        let mut full = None;
+        for (i, row) in shape.iter().enumerate() {
+            let y = position.1 + i as i32;
+            let idx = (y - self.base - 1) as usize;
 
-            self.map.insert(p.clone(), ch.clone());
-        });
+            if idx >= self.rows.len() {
+                self.rows.resize(idx + 1, 0);
+            }
 
-        self.deltas.push(self.highest_y - prev_highest);
+            self.rows[idx] |= row << position.0;
+            self.highest_y = self.highest_y.max(y);
 
+            if self.rows[idx] == self.rules.full_row() {
+                full = Some(idx);
+            }
+        }
+
+        // Nothing can get below a fully blocked row
+        if let Some(idx) = full {
+            self.rows.drain(..idx);
+            self.base += idx as i32;
+        }
+
+        self.deltas.push(self.highest_y - prev_highest);
 
         // Update next shape
-        self.current_shape = self.current_shape.next_shape();
+        self.current_shape = (self.current_shape + 1) % self.shapes.len();
+    }
+
+    fn state(&self) -> State {
+        State {
+            shape: self.current_shape,
+            jet: self.curr_jet_movement,
+            profile: (0..self.rules.width)
+                .map(|x| {
+                    (0..PROFILE_DEPTH)
+                        .find(|depth| self.row(self.highest_y - depth) & (1 << x) != 0)
+                        .unwrap_or(PROFILE_DEPTH)
+                })
+                .collect(),
+        }
+    }
+
+    /// Drops rocks until the cave gets back to an already seen state.
+    ///
+    /// The profile ignores how deep the lowest columns go, so a candidate cycle is
+    /// only accepted once the rocks after it grow the tower the same way again.
+    fn find_cycle(&mut self) -> Cycle {
+        let mut seen: HashMap<State, usize> = HashMap::new();
+
+        loop {
+            let rocks = self.deltas.len();
+
+            if let Some(start) = seen.insert(self.state(), rocks) {
+                let length = rocks - start;
+
+                while self.deltas.len() < rocks + length {
+                    self.tick();
+                }
+
+                if self.deltas[start..rocks] == self.deltas[rocks..rocks + length] {
+                    return Cycle {
+                        start,
+                        length,
+                        height: (self.height_after(rocks) - self.height_after(start)) as i64,
+                    };
+                }
+
+                // Keep looking from where the confirmation stopped
+                seen.clear();
+                continue;
+            }
+
+            self.tick();
+        }
+    }
+
+    /// Tower height once the first `rocks` rocks fell, they must have been simulated
+    fn height_after(&self, rocks: usize) -> i32 {
+        self.deltas[..rocks].iter().sum()
+    }
+}
+
+/// Height of the tower after `rocks` rocks, skipping over repeating cycles
+fn tower_height(jet_movements: Vec<Point>, rules: Rules, rocks: u64) -> i64 {
+    let mut cave = Cave::new(jet_movements, rules);
+    let cycle = cave.find_cycle();
+
+    if rocks <= cave.deltas.len() as u64 {
+        return cave.height_after(rocks as usize) as i64;
     }
 
+    let repeats = (rocks - cycle.start as u64) / cycle.length as u64;
+    let rest = (rocks - cycle.start as u64) % cycle.length as u64;
+
+    cave.height_after(cycle.start + rest as usize) as i64 + repeats as i64 * cycle.height
+}
+
+struct Options {
+    rules: Rules,
+    /// Rows shown when rendering the chamber
+    render: Option<usize>,
+    /// Rocks dropped one step at a time before solving
+    steps: usize,
+}
+
+impl Options {
+    /// Reads `--width <n>`, `--spawn-x <n>`, `--spawn-gap <n>`, `--shapes <file>`,
+    /// `--render <rows>` and `--steps <rocks>` from the command line
+    fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self {
+            rules: Rules::default(),
+            render: None,
+            steps: 0,
+        };
+        let rules = &mut options.rules;
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .unwrap_or_else(|| panic!("Missing value after {}", arg));
+
+            match arg.as_str() {
+                "--width" => rules.width = value.parse().expect("Invalid width"),
+                "--spawn-x" => rules.spawn_x = value.parse().expect("Invalid spawn x"),
+                "--spawn-gap" => rules.spawn_gap = value.parse().expect("Invalid spawn gap"),
+                "--shapes" => {
+                    let input = std::fs::read_to_string(&value)
+                        .unwrap_or_else(|err| panic!("Can't read {}: {}", value, err));
+                    rules.shapes = parse_shapes(&input);
+                }
+                "--render" => options.render = Some(value.parse().expect("Invalid rows")),
+                "--steps" => options.steps = value.parse().expect("Invalid rocks"),
+                _ => panic!("Unknown argument: {}", arg),
+            }
+        }
+
+        options
+    }
 }
 
 fn main() {
     let input = include_str!("../input");
+    let options = Options::from_args(std::env::args().skip(1));
+    let rules = options.rules.clone();
 
     let movements: Vec<Point> = input
         .chars()
@@ -240,59 +327,34 @@ fn main() {
         .map(|it| it.into())
         .collect();
 
+    // Step by step, like the puzzle text
+    if options.steps > 0 {
+        let rows = options.render.unwrap_or(20);
+        let mut cave = Cave::new(movements.clone(), rules.clone());
+
+        for _ in 0..options.steps {
+            cave.tick_with(|cave, step, falling| {
+                println!("{}", step);
+                println!("{}", render(cave, rows, Some(falling)));
+                println!();
+            });
+        }
+    }
+
     // Part 1
-    let mut cave = Cave::new(movements.clone());
+    let mut cave = Cave::new(movements.clone(), rules.clone());
 
     for _ in 0..2022 {
         cave.tick();
     }
     println!("Part 1 tallest tower: {}", cave.highest_y);
 
-    // Part 2
-    let mut cave = Cave::new(movements.clone());
-
-    // Run a couple of times to detect cycle
-    for _ in 0..3000 {
-        cave.tick();
+    if let Some(rows) = options.render {
+        println!("{}", render(&cave, rows, None));
     }
 
-    // Detect cycle tring to find a window that match
-    let (offset, size) = (0..500)
-        .find_map(|offset| {
-            let delta_iter = cave.deltas.iter().skip(offset);
-
-            let size = (2..=2500).find(|size| {
-                let window = cave.deltas[offset..offset + size].iter().cycle();
-
-                delta_iter.clone().zip(window).all(|(a, b)| a == b)
-            });
-
-            size.map(|size| (offset, size))
-        })
-        .unwrap();
-
-    let mut target = 1_000_000_000_000i64;
-    let deltas = cave.deltas.clone();
-    let offset_delta = deltas.iter().by_ref().take(offset).sum::<i32>();
-
-    // Decrease offset
-    target -= offset as i64;
-
-    // Get only the repeating values
-    let cycle_deltas: Vec<i32> = deltas.iter().take(size).copied().collect();
-
-    // Sum whole cycle
-    let cycle_sum = cycle_deltas.iter().sum::<i32>();
-
-    // Check how many times the cycle can repeat until get to target
-    let cycle_count = target / size as i64;
-    target %= size as i64;
-
-    // Calculate the rest
-    let rest = cycle_deltas.into_iter().take(target as usize).sum::<i32>();
-
-    // Calculate the total height
-    let height: i64 = offset_delta as i64 + cycle_count as i64 * cycle_sum as i64 + rest as i64;
+    // Part 2
+    let height = tower_height(movements, rules, 1_000_000_000_000);
 
     println!("Part 2 tallest tower: {}", height);
 }
@@ -313,7 +375,7 @@ mod tests {
     #[test]
     fn relative_to() {
         let a = Point::new(10, 10);
-        let shape = RockShape::A;
+        let shape = &Rules::default().shapes[0];
         let result = vec![
             Point::new(10, 10),
             Point::new(11, 10),
@@ -323,4 +385,222 @@ mod tests {
 
         assert_eq!(shape.relative_to(a), result);
     }
+
+    fn parse(input: &str) -> Vec<Point> {
+        input
+            .chars()
+            .filter(|it| it.is_ascii_punctuation())
+            .map(|it| it.into())
+            .collect()
+    }
+
+    fn simulate(jet_movements: Vec<Point>, rules: Rules, rocks: usize) -> i32 {
+        let mut cave = Cave::new(jet_movements, rules);
+        for _ in 0..rocks {
+            cave.tick();
+        }
+
+        cave.highest_y
+    }
+
+    #[test]
+    fn shape_rows() {
+        let rows: Vec<Vec<u32>> = Rules::default().shapes.iter().map(|it| it.rows()).collect();
+
+        assert_eq!(
+            rows,
+            vec![
+                vec![0b1111],
+                vec![0b010, 0b111, 0b010],
+                vec![0b111, 0b100, 0b100],
+                vec![0b1, 0b1, 0b1, 0b1],
+                vec![0b11, 0b11],
+            ]
+        );
+    }
+
+    #[test]
+    fn truncate_full_rows() {
+        let mut cave = Cave::new(parse(include_str!("../input")), Rules::default());
+        for _ in 0..100_000 {
+            cave.tick();
+        }
+
+        // Memory stays bounded while the tower keeps growing
+        assert!(cave.rows.len() < 1000, "{} rows kept", cave.rows.len());
+        assert_eq!(
+            cave.base as usize + cave.rows.len(),
+            cave.highest_y as usize
+        );
+    }
+
+    #[test]
+    fn example_cycle() {
+        let mut cave = Cave::new(parse(include_str!("../example")), Rules::default());
+        let cycle = cave.find_cycle();
+
+        // Jet pattern has 40 pushes, rocks repeat every 35 of them
+        assert_eq!(cycle.length, 35);
+        assert_eq!(cycle.height, 53);
+    }
+
+    #[test]
+    fn example_tower_height() {
+        let jets = parse(include_str!("../example"));
+
+        let rules = Rules::default();
+
+        assert_eq!(tower_height(jets.clone(), rules.clone(), 2022), 3068);
+        assert_eq!(tower_height(jets, rules, 1_000_000_000_000), 1514285714288);
+    }
+
+    #[test]
+    fn cycle_matches_simulation() {
+        let wide = Rules {
+            shapes: parse_shapes("###\n\n#.\n##\n\n#\n#"),
+            width: 9,
+            spawn_x: 3,
+            spawn_gap: 2,
+        };
+
+        for rules in [Rules::default(), wide] {
+            cycle_matches_simulation_with(rules);
+        }
+    }
+
+    fn cycle_matches_simulation_with(rules: Rules) {
+        for input in [
+            include_str!("../example"),
+            include_str!("../input"),
+            "<",
+            ">><",
+        ] {
+            let jets = parse(input);
+
+            for rocks in [0, 1, 7, 1000, 5000, 12345] {
+                assert_eq!(
+                    tower_height(jets.clone(), rules.clone(), rocks as u64),
+                    simulate(jets.clone(), rules.clone(), rocks) as i64,
+                    "{} rocks",
+                    rocks
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn parse_shape() {
+        let shape = Shape::from(".#\n##");
+
+        assert_eq!(
+            shape.points,
+            vec![Point::new(1, 1), Point::new(0, 0), Point::new(1, 0)]
+        );
+        assert_eq!(shape.rows(), vec![0b11, 0b10]);
+        assert_eq!(shape.width(), 2);
+    }
+
+    #[test]
+    fn custom_rules() {
+        // A single square in a chamber as wide as it stacks straight up
+        let rules = Rules {
+            shapes: parse_shapes("##\n##"),
+            width: 2,
+            spawn_x: 0,
+            spawn_gap: 3,
+        };
+
+        assert_eq!(simulate(parse("<>"), rules.clone(), 10), 20);
+        assert_eq!(tower_height(parse("<>"), rules, 1_000_000), 2_000_000);
+    }
+
+    #[test]
+    fn options_args() {
+        let args = [
+            "--width",
+            "9",
+            "--spawn-x",
+            "3",
+            "--spawn-gap",
+            "1",
+            "--render",
+            "5",
+        ];
+        let options = Options::from_args(args.map(String::from).into_iter());
+        let rules = options.rules;
+
+        assert_eq!(rules.width, 9);
+        assert_eq!(rules.spawn_x, 3);
+        assert_eq!(rules.spawn_gap, 1);
+        assert_eq!(rules.shapes, Rules::default().shapes);
+        assert!(Rules { width: 3, ..rules }.validate().is_err());
+        assert_eq!(options.render, Some(5));
+        assert_eq!(options.steps, 0);
+    }
+
+    #[test]
+    fn render_example() {
+        let mut cave = Cave::new(parse(include_str!("../example")), Rules::default());
+        cave.tick();
+        cave.tick();
+
+        let expected = "\
+|...#...|
+|..###..|
+|...#...|
+|..####.|
++-------+";
+
+        assert_eq!(render(&cave, 10, None), expected);
+        assert_eq!(render(&cave, 2, None), "|...#...|\n|..###..|");
+    }
+
+    #[test]
+    fn render_steps() {
+        let mut cave = Cave::new(parse(include_str!("../example")), Rules::default());
+        let mut frames = vec![];
+
+        cave.tick_with(|cave, step, falling| {
+            frames.push(format!("{}\n{}", step, render(cave, 10, Some(falling))));
+        });
+
+        // Spawn, then a push and a fall for each of the four rows it goes down
+        assert_eq!(frames.len(), 9);
+        assert_eq!(
+            frames[0],
+            "\
+A new rock begins falling:
+|..@@@@.|
+|.......|
+|.......|
+|.......|
++-------+"
+        );
+        assert_eq!(
+            frames[1],
+            "\
+Jet of gas pushes rock right:
+|...@@@@|
+|.......|
+|.......|
+|.......|
++-------+"
+        );
+        assert_eq!(
+            frames[3],
+            "\
+Jet of gas pushes rock right, but nothing happens:
+|...@@@@|
+|.......|
+|.......|
++-------+"
+        );
+        assert_eq!(
+            frames[8],
+            "\
+Rock falls 1 unit, causing it to come to rest:
+|..@@@@.|
++-------+"
+        );
+    }
 }
@@ -0,0 +1,66 @@
+use crate::*;
+
+/// What happened to the falling rock, described like the puzzle text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Step {
+    Spawn,
+    /// Jet direction and whether the rock moved
+    Push(Point, bool),
+    /// Whether the rock moved, it comes to rest otherwise
+    Fall(bool),
+}
+
+impl std::fmt::Display for Step {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Step::Spawn => write!(f, "A new rock begins falling:"),
+            Step::Push(jet, moved) => {
+                let dir = if *jet == LEFT { "left" } else { "right" };
+                let outcome = if *moved { "" } else { ", but nothing happens" };
+
+                write!(f, "Jet of gas pushes rock {}{}:", dir, outcome)
+            }
+            Step::Fall(true) => write!(f, "Rock falls 1 unit:"),
+            Step::Fall(false) => write!(f, "Rock falls 1 unit, causing it to come to rest:"),
+        }
+    }
+}
+
+/// Draws the top `rows` rows of the chamber, with the falling rock as `@`.
+///
+/// Settled rocks are `#`, the floor is only drawn once it's in view and rows below
+/// a full row are drawn blocked, since they're no longer stored.
+pub fn render(cave: &Cave, rows: usize, falling: Option<(&[u32], &Point)>) -> String {
+    let falling_top = falling.map_or(0, |(shape, pos)| pos.1 + shape.len() as i32 - 1);
+    let top = cave.highest_y.max(falling_top);
+    let bottom = (top - rows as i32 + 1).max(1);
+
+    let mut lines: Vec<String> = (bottom..=top)
+        .rev()
+        .map(|y| {
+            let rock = cave.row(y);
+            let moving = match falling {
+                Some((shape, pos)) if y >= pos.1 => shape
+                    .get((y - pos.1) as usize)
+                    .map_or(0, |row| row << pos.0),
+                _ => 0,
+            };
+
+            let cells: String = (0..cave.rules.width)
+                .map(|x| match () {
+                    _ if moving & (1 << x) != 0 => '@',
+                    _ if rock & (1 << x) != 0 => '#',
+                    _ => '.',
+                })
+                .collect();
+
+            format!("|{}|", cells)
+        })
+        .collect();
+
+    if bottom == 1 {
+        lines.push(format!("+{}+", "-".repeat(cave.rules.width as usize)));
+    }
+
+    lines.join("\n")
+}
@@ -0,0 +1,128 @@
+use crate::Point;
+
+/// Rock shape, with its bottom left corner at `(0, 0)`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Shape {
+    pub points: Vec<Point>,
+}
+
+impl From<&str> for Shape {
+    /// Parses the puzzle's art, `#` for rock and `.` for air, the last line at the bottom
+    fn from(input: &str) -> Self {
+        let lines: Vec<&str> = input.lines().filter(|it| !it.trim().is_empty()).collect();
+        let height = lines.len() as i32;
+
+        let points: Vec<Point> = lines
+            .iter()
+            .enumerate()
+            .flat_map(|(i, line)| {
+                line.trim()
+                    .chars()
+                    .enumerate()
+                    .filter(|(_, ch)| *ch == '#')
+                    .map(move |(x, _)| Point::new(x as i32, height - 1 - i as i32))
+            })
+            .collect();
+
+        assert!(!points.is_empty(), "Empty shape: {:?}", input);
+
+        Self { points }
+    }
+}
+
+impl Shape {
+    /// Bitmask of each row of the shape, from the bottom one
+    pub fn rows(&self) -> Vec<u32> {
+        let height = self.points.iter().map(|it| it.1).max().unwrap() + 1;
+
+        (0..height)
+            .map(|y| {
+                self.points
+                    .iter()
+                    .filter(|it| it.1 == y)
+                    .fold(0, |row, it| row | 1 << it.0)
+            })
+            .collect()
+    }
+
+    pub fn width(&self) -> i32 {
+        self.points.iter().map(|it| it.0).max().unwrap() + 1
+    }
+
+    #[cfg(test)]
+    pub fn relative_to(&self, point: Point) -> Vec<Point> {
+        self.points
+            .iter()
+            .map(|it| {
+                let mut new = it.clone();
+                new.apply(&point);
+
+                new
+            })
+            .collect()
+    }
+}
+
+/// Shapes separated by empty lines
+pub fn parse_shapes(input: &str) -> Vec<Shape> {
+    input
+        .replace("\r\n", "\n")
+        .split("\n\n")
+        .filter(|it| !it.trim().is_empty())
+        .map(Shape::from)
+        .collect()
+}
+
+/// How rocks fall in the chamber
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rules {
+    /// Shapes falling in order, repeating from the first one
+    pub shapes: Vec<Shape>,
+    /// Columns in the chamber, at most 31
+    pub width: i32,
+    /// Columns between the left wall and a new rock
+    pub spawn_x: i32,
+    /// Empty rows between the highest rock and a new rock
+    pub spawn_gap: i32,
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Self {
+            shapes: parse_shapes(include_str!("../shapes")),
+            width: 7,
+            spawn_x: 2,
+            spawn_gap: 3,
+        }
+    }
+}
+
+impl Rules {
+    /// Row with every column blocked
+    pub fn full_row(&self) -> u32 {
+        (1 << self.width) - 1
+    }
+
+    /// Checks every shape fits in the chamber when spawned
+    pub fn validate(&self) -> Result<(), String> {
+        if self.width < 1 || self.width > 31 {
+            return Err(format!(
+                "Width must be between 1 and 31, got {}",
+                self.width
+            ));
+        }
+
+        if self.shapes.is_empty() {
+            return Err("No shapes".to_string());
+        }
+
+        match self
+            .shapes
+            .iter()
+            .find(|it| self.spawn_x < 0 || self.spawn_x + it.width() > self.width)
+        {
+            Some(shape) => Err(format!("Shape doesn't fit when spawned: {:?}", shape)),
+            None => Ok(()),
+        }
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashSet;
+
+use crate::{extract_boundaries, Point, SIDES};
+
+/// The six cubes sharing a face with `point`
+pub fn neighbours(point: &Point) -> impl Iterator<Item = Point> + '_ {
+    SIDES.iter().map(move |dir| Point {
+        x: point.x + dir.x,
+        y: point.y + dir.y,
+        z: point.z + dir.z,
+    })
+}
+
+/// Dense occupancy grid, one bit per cube of the box `min..=max`
+#[derive(Clone, Debug)]
+pub struct Grid {
+    pub min: Point,
+    pub max: Point,
+    dims: [usize; 3],
+    bits: Vec<u64>,
+}
+
+impl Grid {
+    /// Empty grid covering `min..=max`
+    pub fn empty(min: Point, max: Point) -> Self {
+        let dims = [
+            (max.x - min.x + 1) as usize,
+            (max.y - min.y + 1) as usize,
+            (max.z - min.z + 1) as usize,
+        ];
+        let len = dims.iter().product::<usize>();
+
+        Self {
+            min,
+            max,
+            dims,
+            bits: vec![0; len.div_ceil(64)],
+        }
+    }
+
+    /// Grid with every cube set, padded with a layer of empty cubes all around
+    /// so the outside is connected
+    pub fn new(cubes: &HashSet<Point>) -> Self {
+        let (min, max) = match cubes.is_empty() {
+            true => (Point::new(0, 0, 0), Point::new(0, 0, 0)),
+            false => extract_boundaries(cubes),
+        };
+
+        let mut grid = Self::empty(min + Point::new(-1, -1, -1), max + Point::new(1, 1, 1));
+        for cube in cubes {
+            grid.insert(cube);
+        }
+
+        grid
+    }
+
+    /// Empty grid over the same box
+    pub fn cleared(&self) -> Self {
+        Self::empty(self.min.clone(), self.max.clone())
+    }
+
+    fn index(&self, point: &Point) -> Option<usize> {
+        let x = usize::try_from(point.x - self.min.x).ok()?;
+        let y = usize::try_from(point.y - self.min.y).ok()?;
+        let z = usize::try_from(point.z - self.min.z).ok()?;
+
+        if x >= self.dims[0] || y >= self.dims[1] || z >= self.dims[2] {
+            return None;
+        }
+
+        Some((x * self.dims[1] + y) * self.dims[2] + z)
+    }
+
+    pub fn in_bounds(&self, point: &Point) -> bool {
+        self.index(point).is_some()
+    }
+
+    /// Whether the cube is set, anything out of the box is empty
+    pub fn contains(&self, point: &Point) -> bool {
+        self.index(point)
+            .is_some_and(|i| self.bits[i / 64] & (1 << (i % 64)) != 0)
+    }
+
+    /// Sets the cube, returning whether it was empty
+    pub fn insert(&mut self, point: &Point) -> bool {
+        let i = self
+            .index(point)
+            .unwrap_or_else(|| panic!("Out of the grid: {:?}", point));
+        let was_empty = self.bits[i / 64] & (1 << (i % 64)) == 0;
+        self.bits[i / 64] |= 1 << (i % 64);
+
+        was_empty
+    }
+
+    /// Every point of the box, set or not
+    pub fn cells(&self) -> impl Iterator<Item = Point> + '_ {
+        (self.min.x..=self.max.x).flat_map(move |x| {
+            (self.min.y..=self.max.y)
+                .flat_map(move |y| (self.min.z..=self.max.z).map(move |z| Point::new(x, y, z)))
+        })
+    }
+
+    /// Every set cube
+    pub fn points(&self) -> impl Iterator<Item = Point> + '_ {
+        self.cells().filter(|it| self.contains(it))
+    }
+
+    /// Empty cubes connected to `start` through other empty cubes, within the box
+    pub fn flood(&self, start: &Point) -> Grid {
+        let mut visited = self.cleared();
+        self.flood_into(start, &mut visited, |_| {});
+
+        visited
+    }
+
+    /// Floods from `start` into `visited`, a grid over the same box, without crossing
+    /// cubes already visited. `on_cube` is called once for every newly reached cube.
+    pub fn flood_into(&self, start: &Point, visited: &mut Grid, mut on_cube: impl FnMut(&Point)) {
+        if !visited.insert(start) {
+            return;
+        }
+
+        let mut stack = vec![start.clone()];
+        on_cube(start);
+
+        while let Some(current) = stack.pop() {
+            for side in neighbours(&current) {
+                if self.in_bounds(&side) && !self.contains(&side) && visited.insert(&side) {
+                    on_cube(&side);
+                    stack.push(side);
+                }
+            }
+        }
+    }
+}
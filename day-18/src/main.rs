@@ -1,6 +1,14 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+mod grid;
+mod mesh;
+mod pocket;
+
+use std::collections::HashSet;
 use std::ops;
 
+use grid::*;
+use mesh::*;
+use pocket::*;
+
 const UP: Point = Point { x: 0, y: 1, z: 0 };
 const DOWN: Point = Point { x: 0, y: -1, z: 0 };
 const LEFT: Point = Point { x: -1, y: 0, z: 0 };
@@ -8,27 +16,19 @@ const RIGHT: Point = Point { x: 1, y: 0, z: 0 };
 const FRONT: Point = Point { x: 0, y: 0, z: 1 };
 const BACK: Point = Point { x: 0, y: 0, z: -1 };
 
+const SIDES: [Point; 6] = [LEFT, RIGHT, UP, DOWN, FRONT, BACK];
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq)]
-struct Point {
+pub struct Point {
     x: i32,
     y: i32,
     z: i32,
 }
 
 impl Point {
-    #[allow(unused)]
     fn new(x: i32, y: i32, z: i32) -> Self {
         Self { x, y, z }
     }
-
-    fn is_inside(&self, min: &Point, max: &Point) -> bool {
-        self.x > min.x
-            && self.x < max.x
-            && self.y > min.y
-            && self.y < max.y
-            && self.z > min.z
-            && self.z < max.z
-    }
 }
 
 impl ops::Add<Point> for Point {
@@ -44,7 +44,7 @@ impl ops::Add<Point> for Point {
 
 impl From<&str> for Point {
     fn from(input: &str) -> Self {
-        let parts: Vec<&str> = input.split(",").collect();
+        let parts: Vec<&str> = input.split(',').collect();
 
         Self {
             x: parts[0].parse().unwrap(),
@@ -54,52 +54,51 @@ impl From<&str> for Point {
     }
 }
 
-// Calculate the visible sides, returning points probably of trapped airs
-fn calc_faces(
-    target: &Point,
-    cubes: &mut HashMap<Point, usize>,
-    visited: &mut HashSet<Point>,
-) -> HashSet<Point> {
-    let mut result: HashSet<Point> = HashSet::new();
-
-    // Check all sides
-    let left = target.clone() + LEFT;
-    let right = target.clone() + RIGHT;
-    let up = target.clone() + UP;
-    let down = target.clone() + DOWN;
-    let front = target.clone() + FRONT;
-    let back = target.clone() + BACK;
-
-    // Current
-    let mut current = cubes.get(target).unwrap().clone();
-
-    // Check all sides
-    for side in vec![left, right, up, down, front, back] {
-        // Check if exist and wasn't visited yet
-        match (cubes.get_mut(&side), visited.get(&side)) {
-            (Some(other), None) => {
-                *other -= 1;
-                current -= 1;
-            }
-            // Probably trapped air
-            (None, _) => {
-                result.insert(side.clone());
-            }
-            _ => {}
-        }
-    }
+/// Faces of the cubes not touching another cube
+fn surface_area(grid: &Grid) -> usize {
+    grid.points()
+        .map(|cube| neighbours(&cube).filter(|it| !grid.contains(it)).count())
+        .sum()
+}
 
-    // Update
-    cubes.insert(target.clone(), current);
+/// Faces of the cubes touching the air around the droplet
+fn exterior_surface_area(grid: &Grid) -> usize {
+    // The padding around the droplet is outside air
+    let outside = grid.flood(&grid.min);
 
-    // Add as visited
-    visited.insert(target.clone());
+    grid.points()
+        .map(|cube| neighbours(&cube).filter(|it| outside.contains(it)).count())
+        .sum()
+}
 
-    result
+/// Every face of a cube not touching another cube
+fn exposed_faces(grid: &Grid) -> Vec<Face> {
+    faces_towards(grid, |it| !grid.contains(it))
 }
 
-fn sum_faces(cubes: &HashMap<Point, usize>) -> usize {
-    cubes.into_iter().map(|it| it.1).sum()
+/// Faces reached by the air flowing around the droplet
+fn external_faces(grid: &Grid) -> Vec<Face> {
+    let outside = grid.flood(&grid.min);
+
+    faces_towards(grid, |it| outside.contains(it))
+}
+
+/// Faces of the cubes looking at a point where `showing` holds
+fn faces_towards(grid: &Grid, showing: impl Fn(&Point) -> bool) -> Vec<Face> {
+    let mut faces = vec![];
+
+    for cube in grid.points() {
+        for dir in SIDES {
+            if showing(&(cube.clone() + dir.clone())) {
+                faces.push(Face {
+                    cube: cube.clone(),
+                    normal: dir,
+                });
+            }
+        }
+    }
+
+    faces
 }
 
 fn extract_boundaries(cubes: &HashSet<Point>) -> (Point, Point) {
@@ -119,84 +118,107 @@ fn extract_boundaries(cubes: &HashSet<Point>) -> (Point, Point) {
     (min, max)
 }
 
-fn count_external_faces(
-    cubes: &HashSet<Point>,
-    boundaries: (Point, Point),
-) -> usize {
-    let mut counter = 0;
-    let mut visited: HashSet<Point> = HashSet::new();
-    let mut queue: VecDeque<Point> = VecDeque::new();
-    
-    // Add one point outside the real boundaries
-    queue.push_back(Point::new(boundaries.1.x, boundaries.1.y, boundaries.1.z));
-
-    // Increase the boundaries, so we can navigate from outside
-    let mut boundaries = boundaries.clone();
-    let padding = 2;
-    boundaries.0.x -= padding;
-    boundaries.0.y -= padding;
-    boundaries.0.z -= padding;
-    boundaries.1.x += padding;
-    boundaries.1.y += padding;
-    boundaries.1.z += padding;
-
-    while queue.len() > 0 {
-        // Get the next item
-        let current = queue.pop_front().unwrap();
-
-        // Check if was visited
-        if visited.get(&current).is_some() {
-            continue;
-        }
+struct Options {
+    /// Path of the cubes, as `x,y,z` lines or an `.obj` or `.stl` mesh
+    input: Option<String>,
+    /// Where to write the exposed faces, as OBJ or STL depending on the extension
+    export: Option<String>,
+    /// Export only the faces reached from outside
+    external: bool,
+}
 
-        // Check all sides
-        let left = current.clone() + LEFT;
-        let right = current.clone() + RIGHT;
-        let up = current.clone() + UP;
-        let down = current.clone() + DOWN;
-        let front = current.clone() + FRONT;
-        let back = current.clone() + BACK;
-
-        // Check all sides
-        for side in vec![left, right, up, down, front, back] {
-            // If it's a cube, sum the faces
-            if cubes.get(&side).is_some() {
-                counter += 1;
-            } else if side.is_inside(&boundaries.0, &boundaries.1){
-                queue.push_back(side);
+impl Options {
+    /// Reads `--input <path>`, `--export <path>` and `--faces <all|external>`
+    fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self {
+            input: None,
+            export: None,
+            external: false,
+        };
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .unwrap_or_else(|| panic!("Missing value after {}", arg));
+
+            match arg.as_str() {
+                "--input" => options.input = Some(value),
+                "--export" => options.export = Some(value),
+                "--faces" => {
+                    options.external = match value.as_str() {
+                        "all" => false,
+                        "external" => true,
+                        _ => panic!("Unknown faces: {}", value),
+                    }
+                }
+                _ => panic!("Unknown argument: {}", arg),
             }
         }
 
-        // Add as visited
-        visited.insert(current);
+        options
     }
+}
+
+/// Cubes of a droplet, as `x,y,z` lines or a voxel mesh depending on the extension
+fn load(path: &str) -> HashSet<Point> {
+    let input =
+        std::fs::read_to_string(path).unwrap_or_else(|err| panic!("Can't read {}: {}", path, err));
 
-    counter
+    let cubes = if path.ends_with(".obj") {
+        from_obj(&input)
+    } else if path.ends_with(".stl") {
+        from_stl(&input)
+    } else {
+        return input.lines().map(Point::from).collect();
+    };
 
+    cubes.unwrap_or_else(|err| panic!("Can't import {}: {}", path, err))
 }
 
 fn main() {
-    let input = include_str!("../input");
-    let cubes: HashSet<Point> = input
-        .lines()
-        .into_iter()
-        .map(|it| Point::from(it))
-        .collect();
-    let mut visited: HashSet<Point> = HashSet::new();
+    let options = Options::from_args(std::env::args().skip(1));
+    let cubes: HashSet<Point> = match &options.input {
+        Some(path) => load(path),
+        None => include_str!("../input").lines().map(Point::from).collect(),
+    };
+    let grid = Grid::new(&cubes);
 
     // Part 1
-    let mut cubes_faces: HashMap<Point, usize> = cubes.clone().into_iter().map(|it| (it, 6)).collect();
-    for (point, _) in cubes_faces.clone().iter() {
-        calc_faces(point, &mut cubes_faces, &mut visited);
-    }
-
-    // Sum
-    println!("Part 1, sum of showing faces: {}", sum_faces(&cubes_faces));
+    println!("Part 1, sum of showing faces: {}", surface_area(&grid));
 
     // Part 2
-    let boundaries = extract_boundaries(&cubes);
-    let external_faces = count_external_faces(&cubes, boundaries);
-    println!("Part 2, sum of showing faces: {}", external_faces);
+    println!(
+        "Part 2, sum of showing faces: {}",
+        exterior_surface_area(&grid)
+    );
+
+    // Trapped air
+    let pockets = air_pockets(&grid);
+    println!(
+        "{} lava cubes, {} air pockets with {} cubes, droplet volume {}",
+        cubes.len(),
+        pockets.len(),
+        pockets.iter().map(|it| it.volume).sum::<usize>(),
+        droplet_volume(&grid)
+    );
+
+    if let Some(path) = options.export {
+        let faces = if options.external {
+            external_faces(&grid)
+        } else {
+            exposed_faces(&grid)
+        };
+
+        let mesh = if path.ends_with(".stl") {
+            to_stl(&faces, "droplet")
+        } else {
+            to_obj(&faces)
+        };
+
+        std::fs::write(&path, mesh).unwrap_or_else(|err| panic!("Can't write {}: {}", path, err));
+        println!("Exported {} faces to {}", faces.len(), path);
+    }
 }
 
 #[cfg(test)]
@@ -207,32 +229,26 @@ mod tests {
 
     #[test]
     fn check_cube() {
-        let mut visited: HashSet<Point> = HashSet::new();
-        let mut cubes: HashMap<Point, usize> = HashMap::new();
+        let mut cubes: HashSet<Point> = HashSet::new();
 
         // Left
-        cubes.insert(Point::new(-1, 0, 0), 6);
+        cubes.insert(Point::new(-1, 0, 0));
         // Up
-        cubes.insert(Point::new(0, 1, 0), 6);
+        cubes.insert(Point::new(0, 1, 0));
         // Back
-        cubes.insert(Point::new(0, 0, -1), 6);
+        cubes.insert(Point::new(0, 0, -1));
         // Center
-        cubes.insert(Point::new(0, 0, 0), 6);
+        cubes.insert(Point::new(0, 0, 0));
         // Front
-        cubes.insert(Point::new(0, 0, 1), 6);
+        cubes.insert(Point::new(0, 0, 1));
         // Down
-        cubes.insert(Point::new(0, -1, 0), 6);
+        cubes.insert(Point::new(0, -1, 0));
         // Right
-        cubes.insert(Point::new(1, 0, 0), 6);
+        cubes.insert(Point::new(1, 0, 0));
         // Another on right
-        cubes.insert(Point::new(2, 0, 0), 6);
-
-        for (point, _) in cubes.clone().iter() {
-            calc_faces(point, &mut cubes, &mut visited);
-        }
+        cubes.insert(Point::new(2, 0, 0));
 
-        let sum: usize = cubes.iter().map(|it| it.1).sum();
-        assert_eq!(sum, 34);
+        assert_eq!(surface_area(&Grid::new(&cubes)), 34);
     }
 
     #[test]
@@ -246,11 +262,7 @@ mod tests {
     #[test]
     fn example_boundaries() {
         let input = include_str!("../example");
-        let cubes: HashSet<Point> = input
-            .lines()
-            .into_iter()
-            .map(|it| Point::from(it))
-            .collect();
+        let cubes: HashSet<Point> = input.lines().map(Point::from).collect();
 
         let min = Point::new(1, 1, 1);
         let max = Point::new(3, 3, 6);
@@ -260,31 +272,176 @@ mod tests {
     #[test]
     fn example_p1() {
         let input = include_str!("../example");
-        let mut cubes: HashMap<Point, usize> = input
-            .lines()
-            .into_iter()
-            .map(|it| (Point::from(it), 6))
-            .collect();
-        let mut visited: HashSet<Point> = HashSet::new();
+        let cubes: HashSet<Point> = input.lines().map(Point::from).collect();
 
-        // Check all cubes
-        for (point, _) in cubes.clone().iter() {
-            calc_faces(point, &mut cubes, &mut visited);
-        }
-
-        // Sum
-        assert_eq!(sum_faces(&cubes), 64);
+        assert_eq!(surface_area(&Grid::new(&cubes)), 64);
     }
 
     #[test]
     fn example_p2() {
         let input = include_str!("../example");
-        let cubes: HashSet<Point> = input
+        let cubes: HashSet<Point> = input.lines().map(Point::from).collect();
+        assert_eq!(exterior_surface_area(&Grid::new(&cubes)), 58);
+    }
+
+    fn example() -> HashSet<Point> {
+        include_str!("../example")
             .lines()
+            .map(Point::from)
+            .collect()
+    }
+
+    #[test]
+    fn face_corners() {
+        let face = Face {
+            cube: Point::new(1, 2, 3),
+            normal: UP,
+        };
+
+        assert_eq!(
+            face.corners(),
+            [
+                Point::new(1, 3, 3),
+                Point::new(1, 3, 4),
+                Point::new(2, 3, 4),
+                Point::new(2, 3, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn example_faces() {
+        let grid = Grid::new(&example());
+
+        assert_eq!(exposed_faces(&grid).len(), 64);
+        assert_eq!(external_faces(&grid).len(), 58);
+    }
+
+    #[test]
+    fn obj_round_trip() {
+        let cubes = example();
+        let obj = to_obj(&exposed_faces(&Grid::new(&cubes)));
+
+        assert_eq!(obj.lines().filter(|it| it.starts_with("f ")).count(), 64);
+        assert_eq!(from_obj(&obj), Ok(cubes));
+    }
+
+    #[test]
+    fn stl_round_trip() {
+        let cubes = example();
+        let faces = external_faces(&Grid::new(&cubes));
+        let stl = to_stl(&faces, "example");
+
+        assert_eq!(stl.matches("endfacet").count(), 2 * 58);
+
+        // Only the outside is exported, so the air pocket gets filled
+        let mut filled = cubes;
+        filled.insert(Point::new(2, 2, 5));
+        assert_eq!(from_stl(&stl), Ok(filled));
+    }
+
+    #[test]
+    fn import_errors() {
+        assert_eq!(
+            from_obj("v 0 0 x"),
+            Err(MeshError::Parse(1, "v 0 0 x".to_string()))
+        );
+
+        // Quad two units wide
+        let obj = "v 0 0 0\nv 2 0 0\nv 2 2 0\nv 0 2 0\nf 1 2 3 4";
+        assert!(matches!(from_obj(obj), Err(MeshError::NotVoxel(_))));
+    }
+
+    #[test]
+    fn example_pockets() {
+        let cubes = example();
+
+        assert_eq!(
+            air_pockets(&Grid::new(&cubes)),
+            vec![AirPocket {
+                volume: 1,
+                surface: 6,
+                min: Point::new(2, 2, 5),
+                max: Point::new(2, 2, 5),
+            }]
+        );
+        assert_eq!(droplet_volume(&Grid::new(&cubes)), 14);
+    }
+
+    #[test]
+    fn pockets_surface() {
+        // A hollow 4x4x4 shell with a 2x2x2 hole and a single cube hole in a corner
+        let mut cubes: HashSet<Point> = HashSet::new();
+        for x in 0..4 {
+            for y in 0..4 {
+                for z in 0..4 {
+                    cubes.insert(Point::new(x, y, z));
+                }
+            }
+        }
+        for x in 1..3 {
+            for y in 1..3 {
+                for z in 1..3 {
+                    cubes.remove(&Point::new(x, y, z));
+                }
+            }
+        }
+        for x in 10..13 {
+            for y in 10..13 {
+                for z in 10..13 {
+                    if (x, y, z) != (11, 11, 11) {
+                        cubes.insert(Point::new(x, y, z));
+                    }
+                }
+            }
+        }
+
+        let grid = Grid::new(&cubes);
+        let mut pockets = air_pockets(&grid);
+        pockets.sort_by_key(|it| it.volume);
+
+        assert_eq!(pockets.len(), 2);
+        assert_eq!(pockets[0].volume, 1);
+        assert_eq!(pockets[0].surface, 6);
+        assert_eq!(pockets[1].volume, 8);
+        assert_eq!(pockets[1].surface, 24);
+        assert_eq!(
+            (pockets[1].min.clone(), pockets[1].max.clone()),
+            (Point::new(1, 1, 1), Point::new(2, 2, 2))
+        );
+
+        // Faces inside the pockets are the only ones the outside can't see
+        assert_eq!(surface_area(&grid) - exterior_surface_area(&grid), 30);
+        assert_eq!(droplet_volume(&grid), 64 + 27);
+    }
+
+    #[test]
+    fn negative_coordinates() {
+        let cubes: HashSet<Point> = example()
             .into_iter()
-            .map(|it| Point::from(it))
+            .map(|it| it + Point::new(-10, -3, -20))
             .collect();
-        let boundaries = extract_boundaries(&cubes);
-        assert_eq!(count_external_faces(&cubes, boundaries), 58);
+        let grid = Grid::new(&cubes);
+
+        assert_eq!(surface_area(&grid), 64);
+        assert_eq!(exterior_surface_area(&grid), 58);
+        assert_eq!(air_pockets(&grid)[0].min, Point::new(-8, -1, -15));
+    }
+
+    #[test]
+    fn grid_bounds() {
+        let cubes = HashSet::from([Point::new(-1, 0, 5), Point::new(2, 0, 5)]);
+        let mut grid = Grid::new(&cubes);
+
+        assert_eq!(grid.min, Point::new(-2, -1, 4));
+        assert_eq!(grid.max, Point::new(3, 1, 6));
+        assert!(grid.contains(&Point::new(-1, 0, 5)));
+        assert!(!grid.contains(&Point::new(0, 0, 5)));
+        assert!(!grid.contains(&Point::new(-100, 0, 5)));
+        assert_eq!(grid.points().count(), 2);
+
+        assert!(grid.insert(&Point::new(0, 0, 5)));
+        assert!(!grid.insert(&Point::new(0, 0, 5)));
+        assert_eq!(grid.cells().count(), 6 * 3 * 3);
     }
 }
@@ -0,0 +1,275 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::Point;
+
+/// Square side of a cube, facing away from it along `normal`
+#[derive(Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Face {
+    pub cube: Point,
+    pub normal: Point,
+}
+
+impl Face {
+    /// Corners of the face, counter-clockwise when looking at it from outside
+    pub fn corners(&self) -> [Point; 4] {
+        let n = &self.normal;
+
+        // Tangent axes with `u x v = normal`
+        let (u, v) = match (n.x, n.y, n.z) {
+            (1, 0, 0) => (Point::new(0, 1, 0), Point::new(0, 0, 1)),
+            (-1, 0, 0) => (Point::new(0, 0, 1), Point::new(0, 1, 0)),
+            (0, 1, 0) => (Point::new(0, 0, 1), Point::new(1, 0, 0)),
+            (0, -1, 0) => (Point::new(1, 0, 0), Point::new(0, 0, 1)),
+            (0, 0, 1) => (Point::new(1, 0, 0), Point::new(0, 1, 0)),
+            (0, 0, -1) => (Point::new(0, 1, 0), Point::new(1, 0, 0)),
+            _ => panic!("Invalid face normal: {:?}", n),
+        };
+
+        // Faces with a positive normal are on the far side of the cube
+        let base = Point::new(
+            self.cube.x + n.x.max(0),
+            self.cube.y + n.y.max(0),
+            self.cube.z + n.z.max(0),
+        );
+
+        [
+            base.clone(),
+            base.clone() + u.clone(),
+            base.clone() + u.clone() + v.clone(),
+            base + v,
+        ]
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum MeshError {
+    /// Line number and content of a line that couldn't be read
+    Parse(usize, String),
+    /// Face not lying on the unit grid, as its vertices
+    NotVoxel(Vec<[f64; 3]>),
+}
+
+impl std::fmt::Display for MeshError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MeshError::Parse(line, content) => write!(f, "invalid line {}: {}", line, content),
+            MeshError::NotVoxel(vertices) => {
+                write!(f, "face is not a side of a unit cube: {:?}", vertices)
+            }
+        }
+    }
+}
+
+/// Wavefront OBJ mesh with a quad for every face, sharing vertices between them
+pub fn to_obj(faces: &[Face]) -> String {
+    let mut vertices: HashMap<Point, usize> = HashMap::new();
+    let mut lines = vec![];
+    let mut quads = vec![];
+
+    for face in faces {
+        let indices: Vec<String> = face
+            .corners()
+            .into_iter()
+            .map(|corner| {
+                let next = vertices.len() + 1;
+                let index = *vertices.entry(corner.clone()).or_insert_with(|| {
+                    lines.push(format!("v {} {} {}", corner.x, corner.y, corner.z));
+                    next
+                });
+
+                index.to_string()
+            })
+            .collect();
+
+        quads.push(format!("f {}", indices.join(" ")));
+    }
+
+    lines.extend(quads);
+    lines.join("\n") + "\n"
+}
+
+/// ASCII STL mesh with two triangles for every face
+pub fn to_stl(faces: &[Face], name: &str) -> String {
+    let mut lines = vec![format!("solid {}", name)];
+
+    for face in faces {
+        let n = &face.normal;
+        let [a, b, c, d] = face.corners();
+
+        for triangle in [[&a, &b, &c], [&a, &c, &d]] {
+            lines.push(format!("  facet normal {} {} {}", n.x, n.y, n.z));
+            lines.push("    outer loop".to_string());
+            for p in triangle {
+                lines.push(format!("      vertex {} {} {}", p.x, p.y, p.z));
+            }
+            lines.push("    endloop".to_string());
+            lines.push("  endfacet".to_string());
+        }
+    }
+
+    lines.push(format!("endsolid {}", name));
+    lines.join("\n") + "\n"
+}
+
+fn parse_vertex<'a>(
+    values: impl Iterator<Item = &'a str>,
+    line: (usize, &str),
+) -> Result<[f64; 3], MeshError> {
+    let error = || MeshError::Parse(line.0 + 1, line.1.to_string());
+    let values: Vec<f64> = values
+        .map(|it| it.parse().map_err(|_| error()))
+        .collect::<Result<_, _>>()?;
+
+    match values[..] {
+        [x, y, z] => Ok([x, y, z]),
+        _ => Err(error()),
+    }
+}
+
+/// Polygons of an OBJ mesh, only `v` and `f` lines are used
+fn obj_polygons(input: &str) -> Result<Vec<Vec<[f64; 3]>>, MeshError> {
+    let mut vertices = vec![];
+    let mut polygons = vec![];
+
+    for (i, line) in input.lines().enumerate() {
+        let mut parts = line.split_whitespace();
+        let error = || MeshError::Parse(i + 1, line.to_string());
+
+        match parts.next() {
+            Some("v") => vertices.push(parse_vertex(parts.take(3), (i, line))?),
+            Some("f") => {
+                // Indices may come with texture and normal ones as `v/vt/vn`
+                let polygon = parts
+                    .map(|it| {
+                        let index: usize = it.split('/').next().unwrap().parse().ok()?;
+                        vertices.get(index.checked_sub(1)?).copied()
+                    })
+                    .collect::<Option<Vec<_>>>()
+                    .ok_or_else(error)?;
+
+                polygons.push(polygon);
+            }
+            _ => {}
+        }
+    }
+
+    Ok(polygons)
+}
+
+/// Triangles of an ASCII STL mesh
+fn stl_polygons(input: &str) -> Result<Vec<Vec<[f64; 3]>>, MeshError> {
+    let mut polygons = vec![];
+    let mut current = vec![];
+
+    for (i, line) in input.lines().enumerate() {
+        let mut parts = line.split_whitespace();
+
+        match parts.next() {
+            Some("vertex") => current.push(parse_vertex(parts, (i, line))?),
+            Some("endfacet") => polygons.push(std::mem::take(&mut current)),
+            _ => {}
+        }
+    }
+
+    Ok(polygons)
+}
+
+/// Side of a unit cube a polygon lies on, using its winding for the normal
+fn polygon_face(polygon: &[[f64; 3]]) -> Option<Face> {
+    if polygon.len() < 3 {
+        return None;
+    }
+
+    // Newell's method, exact for planar polygons
+    let mut normal = [0.0; 3];
+    for (a, b) in polygon.iter().zip(polygon.iter().cycle().skip(1)) {
+        normal[0] += (a[1] - b[1]) * (a[2] + b[2]);
+        normal[1] += (a[2] - b[2]) * (a[0] + b[0]);
+        normal[2] += (a[0] - b[0]) * (a[1] + b[1]);
+    }
+
+    let axis = (0..3).find(|k| normal[*k] != 0.0)?;
+    if (0..3).any(|k| k != axis && normal[k] != 0.0) {
+        return None;
+    }
+
+    let mut n = [0; 3];
+    n[axis] = normal[axis].signum() as i32;
+
+    // The centroid is inside the face, half a cube behind it is the cube's center
+    let len = polygon.len() as f64;
+    let mut cell = [0; 3];
+    for k in 0..3 {
+        let centroid = polygon.iter().map(|p| p[k]).sum::<f64>() / len;
+        cell[k] = (centroid - n[k] as f64 * 0.5).floor() as i32;
+    }
+
+    let face = Face {
+        cube: Point::new(cell[0], cell[1], cell[2]),
+        normal: Point::new(n[0], n[1], n[2]),
+    };
+
+    // Every vertex must be on the face
+    let corners = face.corners();
+    let on_face = polygon.iter().all(|p| {
+        let min = &corners[0];
+        let max = &corners[2];
+        let inside =
+            |k: usize, lo: i32, hi: i32| p[k] >= lo.min(hi) as f64 && p[k] <= lo.max(hi) as f64;
+
+        inside(0, min.x, max.x) && inside(1, min.y, max.y) && inside(2, min.z, max.z)
+    });
+
+    on_face.then_some(face)
+}
+
+/// Cubes enclosed by the faces of a voxel mesh.
+///
+/// Every column along `x` is filled between a face looking left, where it enters
+/// the droplet, and the next one looking right, where it leaves it.
+fn voxelise(polygons: Vec<Vec<[f64; 3]>>) -> Result<HashSet<Point>, MeshError> {
+    let mut faces: HashSet<Face> = HashSet::new();
+
+    for polygon in polygons {
+        match polygon_face(&polygon) {
+            Some(face) => faces.insert(face),
+            None => return Err(MeshError::NotVoxel(polygon)),
+        };
+    }
+
+    let mut columns: HashMap<(i32, i32), Vec<(i32, i32)>> = HashMap::new();
+    for face in faces.iter().filter(|it| it.normal.x != 0) {
+        let column = columns.entry((face.cube.y, face.cube.z)).or_default();
+        column.push((face.cube.x, face.normal.x));
+    }
+
+    let mut cubes = HashSet::new();
+    for ((y, z), mut column) in columns {
+        column.sort_unstable();
+
+        let mut start = None;
+        for (x, normal) in column {
+            match (normal, start) {
+                (-1, None) => start = Some(x),
+                (1, Some(from)) => {
+                    cubes.extend((from..=x).map(|x| Point::new(x, y, z)));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+
+    // Cubes only seen from above, below, in front or behind are still part of it
+    cubes.extend(faces.into_iter().map(|it| it.cube));
+
+    Ok(cubes)
+}
+
+pub fn from_obj(input: &str) -> Result<HashSet<Point>, MeshError> {
+    voxelise(obj_polygons(input)?)
+}
+
+pub fn from_stl(input: &str) -> Result<HashSet<Point>, MeshError> {
+    voxelise(stl_polygons(input)?)
+}
@@ -0,0 +1,58 @@
+use crate::grid::*;
+use crate::Point;
+
+/// Air trapped inside the droplet, connected through its faces
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct AirPocket {
+    /// Number of air cubes
+    pub volume: usize,
+    /// Lava faces around the pocket
+    pub surface: usize,
+    /// Corners of the bounding box, inclusive
+    pub min: Point,
+    pub max: Point,
+}
+
+/// Every pocket of air the outside can't reach, in no particular order
+pub fn air_pockets(grid: &Grid) -> Vec<AirPocket> {
+    // The padding around the droplet is outside air
+    let mut seen = grid.cleared();
+    grid.flood_into(&grid.min, &mut seen, |_| {});
+
+    let mut pockets = vec![];
+    for point in grid.cells() {
+        if grid.contains(&point) || seen.contains(&point) {
+            continue;
+        }
+
+        let mut air = AirPocket {
+            volume: 0,
+            surface: 0,
+            min: point.clone(),
+            max: point.clone(),
+        };
+
+        grid.flood_into(&point, &mut seen, |cube| {
+            air.volume += 1;
+            air.surface += neighbours(cube).filter(|it| grid.contains(it)).count();
+
+            air.min.x = air.min.x.min(cube.x);
+            air.min.y = air.min.y.min(cube.y);
+            air.min.z = air.min.z.min(cube.z);
+            air.max.x = air.max.x.max(cube.x);
+            air.max.y = air.max.y.max(cube.y);
+            air.max.z = air.max.z.max(cube.z);
+        });
+
+        pockets.push(air);
+    }
+
+    pockets
+}
+
+/// Cubes enclosed by the droplet's outer surface, lava and trapped air
+pub fn droplet_volume(grid: &Grid) -> usize {
+    let outside = grid.flood(&grid.min);
+
+    grid.cells().filter(|it| !outside.contains(it)).count()
+}
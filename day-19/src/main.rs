@@ -1,327 +1,285 @@
 #[macro_use]
 extern crate lazy_static;
-use std::collections::HashMap;
+mod solver;
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
 
 use regex::Regex;
+use solver::*;
 
+/// Robot collecting a resource, built by spending others
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Resources {
-    ore: usize,
-    clay: usize,
-    obsidian: usize,
+pub struct Recipe {
+    /// Resource collected by the robot
+    pub robot: usize,
+    /// Amount of every resource spent to build it
+    pub cost: Vec<usize>,
 }
 
+/// Robot recipes over named resources, a resource is referred to by its index in `resources`
 #[derive(Debug, Clone, PartialEq, Eq)]
-struct Blueprint {
-    id: usize,
-    ore_robot_cost: Resources,
-    clay_robot_cost: Resources,
-    obsidian_robot_cost: Resources,
-    geode_robot_cost: Resources,
+pub struct Blueprint {
+    pub id: usize,
+    pub resources: Vec<String>,
+    pub recipes: Vec<Recipe>,
 }
 
 impl From<&str> for Blueprint {
+    /// Parses `Blueprint <id>:` followed by any number of sentences like
+    /// `Each <resource> robot costs <n> <resource> and <n> <resource>.`
     fn from(input: &str) -> Self {
         lazy_static! {
-            static ref RE: Regex = Regex::new(r"Blueprint ([0-9]+): Each ore robot costs ([0-9]+) ore\. Each clay robot costs ([0-9]+) ore\. Each obsidian robot costs ([0-9]+) ore and ([0-9]+) clay\. Each geode robot costs ([0-9]+) ore and ([0-9]+) obsidian\.").unwrap();
-        }
-
-        let captures = RE.captures(input).unwrap();
-
-        Blueprint {
-            id: captures.get(1).unwrap().as_str().parse().unwrap(),
-            ore_robot_cost: Resources {
-                ore: captures.get(2).unwrap().as_str().parse().unwrap(),
-                clay: 0,
-                obsidian: 0,
-            },
-            clay_robot_cost: Resources {
-                ore: captures.get(3).unwrap().as_str().parse().unwrap(),
-                clay: 0,
-                obsidian: 0,
-            },
-            obsidian_robot_cost: Resources {
-                ore: captures.get(4).unwrap().as_str().parse().unwrap(),
-                clay: captures.get(5).unwrap().as_str().parse().unwrap(),
-                obsidian: 0,
-            },
-            geode_robot_cost: Resources {
-                ore: captures.get(6).unwrap().as_str().parse().unwrap(),
-                clay: 0,
-                obsidian: captures.get(7).unwrap().as_str().parse().unwrap(),
-            },
+            static ref ID: Regex = Regex::new(r"Blueprint ([0-9]+):").unwrap();
+            static ref ROBOT: Regex = Regex::new(r"Each (\w+) robot costs ([^.]+)\.").unwrap();
+            static ref COST: Regex = Regex::new(r"([0-9]+) (\w+)").unwrap();
         }
-    }
-}
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash)]
-struct State {
-    time: usize,
-    ore: usize,
-    clay: usize,
-    obsidian: usize,
-    geode: usize,
-    ore_rate: usize,
-    clay_rate: usize,
-    obsidian_rate: usize,
-    geode_rate: usize,
-}
+        let id = ID.captures(input).unwrap().get(1).unwrap().as_str();
+        let mut resources: Vec<String> = vec![];
+        let mut index = |name: &str| match resources.iter().position(|it| it == name) {
+            Some(i) => i,
+            None => {
+                resources.push(name.to_string());
+                resources.len() - 1
+            }
+        };
 
-impl State {
-    fn can_build(&self, cost: &Resources) -> bool {
-        self.ore >= cost.ore && self.clay >= cost.clay && self.obsidian >= cost.obsidian
-    }
+        // Costs are kept by name until every resource is known
+        let mut recipes: Vec<(usize, Vec<(usize, usize)>)> = vec![];
+        for robot in ROBOT.captures_iter(input) {
+            let collects = index(&robot[1]);
+            let cost = COST
+                .captures_iter(&robot[2])
+                .map(|it| (index(&it[2]), it[1].parse().unwrap()))
+                .collect();
 
-    fn collect_resources(&mut self) {
-        // Collect ore
-        if self.ore_rate > 0 {
-            self.ore += self.ore_rate;
+            recipes.push((collects, cost));
         }
 
-        // Collect clay
-        if self.clay_rate > 0 {
-            self.clay += self.clay_rate;
-        }
+        let recipes = recipes
+            .into_iter()
+            .map(|(robot, costs)| {
+                let mut cost = vec![0; resources.len()];
+                for (resource, amount) in costs {
+                    cost[resource] += amount;
+                }
 
-        // Collect obsidian
-        if self.obsidian_rate > 0 {
-            self.obsidian += self.obsidian_rate;
-        }
+                Recipe { robot, cost }
+            })
+            .collect();
 
-        // Collect geode
-        if self.geode_rate > 0 {
-            self.geode += self.geode_rate;
+        Blueprint {
+            id: id.parse().unwrap(),
+            resources,
+            recipes,
         }
     }
+}
 
-    fn consume_resources(&mut self, resources: &Resources) {
-        self.ore -= resources.ore;
-        self.clay -= resources.clay;
-        self.obsidian -= resources.obsidian;
-    }
-
-    fn build_ore(&mut self, cost: &Resources) {
-        self.consume_resources(cost);
-        self.ore_rate += 1;
+impl Blueprint {
+    /// Index of a resource by name
+    pub fn resource(&self, name: &str) -> Option<usize> {
+        self.resources.iter().position(|it| it == name)
     }
 
-    fn build_clay(&mut self, cost: &Resources) {
-        self.consume_resources(cost);
-        self.clay_rate += 1;
-    }
+    /// Index of the recipe building robots collecting a resource
+    pub fn recipe(&self, name: &str) -> Option<usize> {
+        let resource = self.resource(name)?;
 
-    fn build_obsidian(&mut self, cost: &Resources) {
-        self.consume_resources(cost);
-        self.obsidian_rate += 1;
+        self.recipes.iter().position(|it| it.robot == resource)
     }
 
-    fn build_geode(&mut self, cost: &Resources) {
-        self.consume_resources(cost);
-        self.geode_rate += 1;
+    /// Resource to get as much as possible of, geodes or the last robot's resource
+    pub fn target(&self) -> usize {
+        self.resource("geode")
+            .unwrap_or_else(|| self.recipes.last().unwrap().robot)
     }
 }
 
-fn find_max_geodes(blueprint: &Blueprint, initial_state: State) -> usize {
-    let mut queue: Vec<State> = vec![];
-    let mut dp: HashMap<State, usize> = HashMap::new();
-    let mut max_geodes = 0;
-
-    // Calculate max spend per minute by resource
-    let mut max_ore_pm = 0;
-    let mut max_clay_pm = 0;
-    let mut max_obsidian_pm = 0;
-
-    for costs in vec![
-        &blueprint.ore_robot_cost,
-        &blueprint.clay_robot_cost,
-        &blueprint.obsidian_robot_cost,
-        &blueprint.geode_robot_cost,
-    ]
-    .iter()
-    {
-        max_ore_pm = max_ore_pm.max(costs.ore);
-        max_clay_pm = max_clay_pm.max(costs.clay);
-        max_obsidian_pm = max_obsidian_pm.max(costs.obsidian);
-    }
-
-    // Initial state
-    queue.push(initial_state);
-
-    while queue.len() > 0 {
-        let mut state = queue.pop().unwrap();
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct State {
+    time: usize,
+    /// Amount of every resource
+    stock: Vec<usize>,
+    /// Robots collecting every resource
+    robots: Vec<usize>,
+}
 
-        // State seen
-        if dp.get(&state).is_some() {
-            continue;
+impl State {
+    /// Starts with a single ore robot, or one of the first recipe without any ore
+    fn new(blueprint: &Blueprint, time: usize) -> Self {
+        let mut robots = vec![0; blueprint.resources.len()];
+        let first = blueprint
+            .resource("ore")
+            .unwrap_or_else(|| blueprint.recipes[0].robot);
+        robots[first] = 1;
+
+        Self {
+            time,
+            stock: vec![0; blueprint.resources.len()],
+            robots,
         }
+    }
 
-        // Prune state based on possible max geode rate
-        queue = queue
-            .into_iter()
-            .filter(|it| {
-                let max_possible_geodes = max_rate(it.time, it.geode_rate);
-
-                return max_possible_geodes > max_geodes;
-            })
-            .collect();
-
-        // Update dp
-        dp.insert(state.clone(), state.geode);
-
-        // Check
-        let should_build_ore_robot =
-            state.can_build(&blueprint.ore_robot_cost) && state.ore_rate < max_ore_pm;
-        let should_build_clay_robot =
-            state.can_build(&blueprint.clay_robot_cost) && state.clay_rate < max_clay_pm;
-        let should_build_obsidian_robot = state.can_build(&blueprint.obsidian_robot_cost)
-            && state.obsidian_rate < max_obsidian_pm;
-        let should_build_geode_robot = state.can_build(&blueprint.geode_robot_cost);
-
-        // Collect resources
-        state.collect_resources();
-
-        // Decrease minute
-        state.time -= 1;
-
-        // Update max geodes built
-        if state.geode > max_geodes {
-            max_geodes = max_geodes.max(state.geode);
-        }
+    fn can_build(&self, recipe: &Recipe) -> bool {
+        self.stock
+            .iter()
+            .zip(&recipe.cost)
+            .all(|(have, cost)| have >= cost)
+    }
 
-        // Time out
-        if state.time == 0 {
-            continue;
+    /// Collects resources and lets the minutes pass
+    fn collect_for(&mut self, minutes: usize) {
+        for (stock, robots) in self.stock.iter_mut().zip(&self.robots) {
+            *stock += robots * minutes;
         }
+        self.time -= minutes;
+    }
 
-        // Just wait
-        queue.push(state.clone());
-
-        // Build ore robot
-        if should_build_ore_robot {
-            let mut state = state.clone();
-            state.build_ore(&blueprint.ore_robot_cost);
-
-            // Add if we haven't checked this state before
-            if dp.get(&state).is_none() {
-                queue.push(state);
-            }
+    fn consume_resources(&mut self, recipe: &Recipe) {
+        for (stock, cost) in self.stock.iter_mut().zip(&recipe.cost) {
+            *stock -= cost;
         }
+    }
 
-        // Build clay robot
-        if should_build_clay_robot {
-            let mut state = state.clone();
-            state.build_clay(&blueprint.clay_robot_cost);
-
-            // Add if we haven't checked this state before
-            if dp.get(&state).is_none() {
-                queue.push(state);
-            }
-        }
+    fn add_robot(&mut self, recipe: &Recipe) {
+        self.robots[recipe.robot] += 1;
+    }
 
-        // Build obsidian robot
-        if should_build_obsidian_robot {
-            let mut state = state.clone();
-            state.build_obsidian(&blueprint.obsidian_robot_cost);
+    fn build(&mut self, recipe: &Recipe) {
+        self.consume_resources(recipe);
+        self.add_robot(recipe);
+    }
+}
 
-            // Add if we haven't checked this state before
-            if dp.get(&state).is_none() {
-                queue.push(state);
-            }
-        }
+fn find_max_geodes(blueprint: &Blueprint, minutes: usize) -> usize {
+    Solver::new(blueprint, minutes)
+        .best(&State::new(blueprint, minutes))
+        .geodes
+}
 
-        // Build geode robot
-        if should_build_geode_robot {
-            let mut state = state.clone();
-            state.build_geode(&blueprint.geode_robot_cost);
+/// Max geodes of every blueprint, evaluated on as many threads as there are cores
+fn max_geodes_parallel(blueprints: &[Blueprint], minutes: usize) -> Vec<usize> {
+    let workers = thread::available_parallelism()
+        .map_or(1, |it| it.get())
+        .min(blueprints.len());
+    let next = AtomicUsize::new(0);
+    let mut results = vec![0; blueprints.len()];
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut done = vec![];
+
+                    // Take the next blueprint nobody is working on
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(blueprint) = blueprints.get(i) else {
+                            break;
+                        };
+
+                        done.push((i, find_max_geodes(blueprint, minutes)));
+                    }
+
+                    done
+                })
+            })
+            .collect();
 
-            // Add if we haven't checked this state before
-            if dp.get(&state).is_none() {
-                queue.push(state);
+        for handle in handles {
+            for (i, geodes) in handle.join().unwrap() {
+                results[i] = geodes;
             }
         }
-    }
+    });
 
-    max_geodes
+    results
 }
 
-// Calculate max possible rate given a fixed time
-fn max_rate(time: usize, current_rate: usize) -> usize {
-    let mut time = time;
-    let mut total = 0;
-    let mut current_rate = current_rate;
-
-    while time > 0 {
-        total += current_rate;
-        current_rate += 1;
-
-        time -= 1;
-    }
-
-    total
+fn sum_blueprint_quality(blueprints: &[Blueprint], max_geodes: &[usize]) -> usize {
+    blueprints
+        .iter()
+        .zip(max_geodes)
+        .map(|(blueprint, geodes)| blueprint.id * geodes)
+        .sum()
 }
 
-fn sum_blueprint_quality(blueprints: &Vec<Blueprint>, initial_state: State) -> usize {
-    let mut sum = 0;
-
-    for blueprint in blueprints {
-        let max_geodes = find_max_geodes(&blueprint, initial_state.clone());
-        sum += blueprint.id * max_geodes;
-    }
+fn multiply_max_geodes(max_geodes: &[usize]) -> usize {
+    max_geodes.iter().product()
+}
 
-    sum
+struct Options {
+    /// Blueprint to show the best build order of
+    walkthrough: Option<usize>,
 }
 
-fn multiply_max_geodes(blueprints: &Vec<Blueprint>, initial_state: State) -> usize {
-    let mut result = 1;
-    blueprints.iter().for_each(|it| {
-        result *= find_max_geodes(it, initial_state.clone());
-    });
+impl Options {
+    /// Reads `--walkthrough <blueprint id>` from the command line
+    fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self { walkthrough: None };
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .unwrap_or_else(|| panic!("Missing value after {}", arg));
+
+            match arg.as_str() {
+                "--walkthrough" => {
+                    options.walkthrough = Some(value.parse().expect("Invalid blueprint id"))
+                }
+                _ => panic!("Unknown argument: {}", arg),
+            }
+        }
 
-    result
+        options
+    }
 }
 
 fn main() {
-    let input = include_str!("../example");
-    let blueprints: Vec<Blueprint> = input
-        .lines()
-        .into_iter()
-        .map(|line| Blueprint::from(line))
-        .collect();
+    let input = include_str!("../input");
+    let blueprints: Vec<Blueprint> = input.lines().map(Blueprint::from).collect();
+
+    let options = Options::from_args(std::env::args().skip(1));
 
     // Part 1
-    let initial_state = State {
-        time: 24,
-        ore: 0,
-        clay: 0,
-        geode: 0,
-        obsidian: 0,
-        ore_rate: 1,
-        clay_rate: 0,
-        obsidian_rate: 0,
-        geode_rate: 0,
-    };
+    let max_geodes = max_geodes_parallel(&blueprints, 24);
+    for (blueprint, geodes) in blueprints.iter().zip(max_geodes.iter()) {
+        println!(
+            "Blueprint {}: {} geodes, quality level {}",
+            blueprint.id,
+            geodes,
+            blueprint.id * geodes
+        );
+    }
     println!(
         "Part 1: {}",
-        sum_blueprint_quality(&blueprints, initial_state)
+        sum_blueprint_quality(&blueprints, &max_geodes)
     );
 
+    // Build order of a single blueprint
+    if let Some(id) = options.walkthrough {
+        let blueprint = blueprints
+            .iter()
+            .find(|it| it.id == id)
+            .unwrap_or_else(|| panic!("Unknown blueprint: {}", id));
+        let initial_state = State::new(blueprint, 24);
+        let solver = Solver::new(blueprint, 24);
+        let plan = solver.best(&initial_state);
+
+        println!();
+        println!("{}", solver.walkthrough(&initial_state, &plan));
+    }
+
     // Part 2
-    let initial_state = State {
-        time: 32,
-        ore: 0,
-        clay: 0,
-        geode: 0,
-        obsidian: 0,
-        ore_rate: 1,
-        clay_rate: 0,
-        obsidian_rate: 0,
-        geode_rate: 0,
-    };
-    let blueprints = blueprints.into_iter().take(3).collect();
-    println!(
-        "Part 2: {}",
-        multiply_max_geodes(&blueprints, initial_state)
-    );
+    let blueprints: Vec<Blueprint> = blueprints.into_iter().take(3).collect();
+    let max_geodes = max_geodes_parallel(&blueprints, 32);
+    for (blueprint, geodes) in blueprints.iter().zip(max_geodes.iter()) {
+        println!("Blueprint {}: {} geodes", blueprint.id, geodes);
+    }
+    println!("Part 2: {}", multiply_max_geodes(&max_geodes));
 }
 
 #[cfg(test)]
@@ -334,58 +292,255 @@ mod tests {
         let blueprint: Blueprint = input.into();
         let expected = Blueprint {
             id: 1,
-            ore_robot_cost: Resources {
-                ore: 4,
-                clay: 0,
-                obsidian: 0,
-            },
-            clay_robot_cost: Resources {
-                ore: 2,
-                clay: 0,
-                obsidian: 0,
-            },
-            obsidian_robot_cost: Resources {
-                ore: 3,
-                clay: 14,
-                obsidian: 0,
-            },
-            geode_robot_cost: Resources {
-                ore: 2,
-                clay: 0,
-                obsidian: 7,
-            },
+            resources: ["ore", "clay", "obsidian", "geode"]
+                .map(String::from)
+                .to_vec(),
+            recipes: vec![
+                Recipe {
+                    robot: 0,
+                    cost: vec![4, 0, 0, 0],
+                },
+                Recipe {
+                    robot: 1,
+                    cost: vec![2, 0, 0, 0],
+                },
+                Recipe {
+                    robot: 2,
+                    cost: vec![3, 14, 0, 0],
+                },
+                Recipe {
+                    robot: 3,
+                    cost: vec![2, 0, 7, 0],
+                },
+            ],
         };
 
         assert_eq!(blueprint, expected);
+        assert_eq!(blueprint.target(), 3);
+        assert_eq!(blueprint.recipe("obsidian"), Some(2));
+    }
+
+    #[test]
+    fn parse_other_blueprints() {
+        // Costs may refer to resources before their robot is described, over several lines
+        let input = "Blueprint 7:
+  Each clay robot costs 2 ore.
+  Each ore robot costs 3 ore.
+  Each geode robot costs 4 ore and 5 diamond.
+  Each diamond robot costs 1 ore and 2 clay and 3 obsidian.
+  Each obsidian robot costs 6 clay.";
+        let blueprint = Blueprint::from(input);
+
+        assert_eq!(blueprint.id, 7);
+        assert_eq!(
+            blueprint.resources,
+            ["clay", "ore", "geode", "diamond", "obsidian"].map(String::from)
+        );
+        assert_eq!(blueprint.target(), 2);
+        assert_eq!(
+            blueprint.recipes[3],
+            Recipe {
+                robot: 3,
+                cost: vec![2, 1, 0, 0, 3],
+            }
+        );
+
+        // Without geodes, the last robot is the one that matters
+        let blueprint = Blueprint::from(
+            "Blueprint 2: Each ore robot costs 1 ore. Each gold robot costs 3 ore.",
+        );
+        assert_eq!(blueprint.resources[blueprint.target()], "gold");
     }
 
     #[test]
     fn example_part1_blueprint1() {
         let input = include_str!("../example");
-        let blueprints: Vec<Blueprint> = input
-            .lines()
-            .into_iter()
-            .map(|line| Blueprint::from(line))
-            .collect();
-        let initial_state = State {
-            time: 24,
-            ore: 0,
-            clay: 0,
-            geode: 0,
-            obsidian: 0,
-            ore_rate: 1,
-            clay_rate: 0,
-            obsidian_rate: 0,
-            geode_rate: 0,
-        };
+        let blueprints: Vec<Blueprint> = input.lines().map(Blueprint::from).collect();
 
-        let max_geodes = find_max_geodes(blueprints.get(0).unwrap(), initial_state);
+        let max_geodes = find_max_geodes(blueprints.first().unwrap(), 24);
         assert_eq!(max_geodes, 9);
     }
 
     #[test]
-    fn calc_max_rate() {
-        assert_eq!(max_rate(3, 2), 9);
-        assert_eq!(max_rate(1, 1), 1);
+    fn parallel_matches_sequential() {
+        let input = include_str!("../input");
+        let blueprints: Vec<Blueprint> = input.lines().take(4).map(Blueprint::from).collect();
+
+        let sequential: Vec<usize> = blueprints
+            .iter()
+            .map(|it| find_max_geodes(it, 18))
+            .collect();
+        let parallel = max_geodes_parallel(&blueprints, 18);
+
+        assert_eq!(parallel, sequential);
+        assert_eq!(
+            sum_blueprint_quality(&blueprints, &parallel),
+            (1..=4).zip(sequential.iter()).map(|(id, g)| id * g).sum()
+        );
+    }
+
+    /// Every choice on every minute, without any pruning
+    fn brute_force(blueprint: &Blueprint, state: State) -> usize {
+        if state.time == 0 {
+            return state.stock[blueprint.target()];
+        }
+
+        let mut waited = state.clone();
+        waited.collect_for(1);
+        let mut best = brute_force(blueprint, waited);
+
+        for recipe in blueprint.recipes.iter() {
+            if !state.can_build(recipe) {
+                continue;
+            }
+
+            let mut next = state.clone();
+            next.collect_for(1);
+            next.build(recipe);
+
+            best = best.max(brute_force(blueprint, next));
+        }
+
+        best
+    }
+
+    #[test]
+    fn example_max_geodes() {
+        let input = include_str!("../example");
+        let blueprints: Vec<Blueprint> = input.lines().map(Blueprint::from).collect();
+
+        let part1 = max_geodes_parallel(&blueprints, 24);
+        assert_eq!(part1, vec![9, 12]);
+        assert_eq!(sum_blueprint_quality(&blueprints, &part1), 33);
+
+        let part2 = max_geodes_parallel(&blueprints, 32);
+        assert_eq!(part2, vec![56, 62]);
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let input = include_str!("../input");
+
+        for blueprint in input.lines().take(5).map(Blueprint::from) {
+            for time in [10, 14, 16] {
+                assert_eq!(
+                    find_max_geodes(&blueprint, time),
+                    brute_force(&blueprint, State::new(&blueprint, time)),
+                    "Blueprint {} in {} minutes",
+                    blueprint.id,
+                    time
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn other_robots_match_brute_force() {
+        let blueprints = [
+            // Geode robots listed first, obsidian and clay swapped
+            "Blueprint 1: Each ore robot costs 2 ore. Each geode robot costs 2 ore and 3 obsidian. Each obsidian robot costs 2 ore and 4 clay. Each clay robot costs 2 ore.",
+            // An extra resource between obsidian and geodes
+            "Blueprint 2: Each ore robot costs 2 ore. Each clay robot costs 2 ore. Each obsidian robot costs 2 ore and 3 clay. Each diamond robot costs 1 ore and 2 obsidian. Each geode robot costs 2 ore and 2 diamond and 1 clay.",
+        ];
+
+        for blueprint in blueprints.map(Blueprint::from) {
+            for time in [12, 14] {
+                assert_eq!(
+                    find_max_geodes(&blueprint, time),
+                    brute_force(&blueprint, State::new(&blueprint, time)),
+                    "Blueprint {} in {} minutes",
+                    blueprint.id,
+                    time
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ore_robot_listed_later() {
+        let input = include_str!("../example").lines().next().unwrap();
+        let swapped = input.replace(
+            "Each ore robot costs 4 ore. Each clay robot costs 2 ore.",
+            "Each clay robot costs 2 ore. Each ore robot costs 4 ore.",
+        );
+        let blueprint = Blueprint::from(swapped.as_str());
+        assert_eq!(blueprint.resources[blueprint.recipes[0].robot], "clay");
+
+        let state = State::new(&blueprint, 24);
+        assert_eq!(state.robots[blueprint.resource("ore").unwrap()], 1);
+        assert_eq!(find_max_geodes(&blueprint, 24), 9);
+
+        for time in [12, 14] {
+            assert_eq!(
+                find_max_geodes(&blueprint, time),
+                brute_force(&blueprint, State::new(&blueprint, time)),
+                "{} minutes",
+                time
+            );
+        }
+    }
+
+    #[test]
+    fn example_walkthrough() {
+        let blueprint = Blueprint::from(include_str!("../example").lines().next().unwrap());
+        let solver = Solver::new(&blueprint, 24);
+        let initial_state = State::new(&blueprint, 24);
+        let clay = blueprint.recipe("clay").unwrap();
+        let obsidian = blueprint.recipe("obsidian").unwrap();
+        let geode = blueprint.recipe("geode").unwrap();
+
+        // Build order from the puzzle text
+        let plan = Plan {
+            geodes: 9,
+            builds: vec![
+                (3, clay),
+                (5, clay),
+                (7, clay),
+                (11, obsidian),
+                (12, clay),
+                (15, obsidian),
+                (18, geode),
+                (21, geode),
+            ],
+        };
+        let text = solver.walkthrough(&initial_state, &plan);
+
+        assert!(text.starts_with(
+            "\
+== Minute 1 ==
+1 ore-collecting robot collects 1 ore; you now have 1 ore.
+
+== Minute 2 ==
+1 ore-collecting robot collects 1 ore; you now have 2 ore.
+
+== Minute 3 ==
+Spend 2 ore to start building a clay-collecting robot.
+1 ore-collecting robot collects 1 ore; you now have 1 ore.
+The new clay-collecting robot is ready; you now have 1 of them.
+"
+        ));
+        assert!(text.contains(
+            "\
+== Minute 11 ==
+Spend 3 ore and 14 clay to start building an obsidian-collecting robot.
+1 ore-collecting robot collects 1 ore; you now have 2 ore.
+3 clay-collecting robots collect 3 clay; you now have 4 clay.
+The new obsidian-collecting robot is ready; you now have 1 of them.
+"
+        ));
+        assert!(text.ends_with(
+            "\
+== Minute 24 ==
+1 ore-collecting robot collects 1 ore; you now have 6 ore.
+4 clay-collecting robots collect 4 clay; you now have 41 clay.
+2 obsidian-collecting robots collect 2 obsidian; you now have 8 obsidian.
+2 geode-cracking robots crack 2 geodes; you now have 9 open geodes.
+"
+        ));
+
+        // The plan found replays to the same amount of geodes
+        let best = solver.best(&initial_state);
+        assert_eq!(best.geodes, 9);
+        solver.walkthrough(&initial_state, &best);
     }
 }
@@ -0,0 +1,284 @@
+use std::fmt::Write;
+
+use crate::{Blueprint, State};
+
+/// Most of the target a blueprint can get, and the minute each robot starts being
+/// built with the index of its recipe
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct Plan {
+    pub geodes: usize,
+    pub builds: Vec<(usize, usize)>,
+}
+
+/// Branch and bound search over which robot to build next.
+///
+/// Instead of deciding what to do every minute, each branch waits until the next
+/// robot is affordable and builds it, so idle minutes are skipped.
+pub struct Solver<'a> {
+    blueprint: &'a Blueprint,
+    minutes: usize,
+    /// Resource to get as much as possible of
+    target: usize,
+    /// Most of each resource a single robot costs, there's no use in collecting more
+    /// than that per minute since only one robot is built at a time
+    max_cost: Vec<usize>,
+    /// Recipes to try building next, the target's first and then the later ones,
+    /// so good plans are found early
+    order: Vec<usize>,
+}
+
+impl<'a> Solver<'a> {
+    pub fn new(blueprint: &'a Blueprint, minutes: usize) -> Self {
+        let target = blueprint.target();
+        let mut max_cost = vec![0; blueprint.resources.len()];
+
+        for recipe in blueprint.recipes.iter() {
+            for (max, cost) in max_cost.iter_mut().zip(&recipe.cost) {
+                *max = (*max).max(*cost);
+            }
+        }
+
+        let mut order: Vec<usize> = (0..blueprint.recipes.len()).rev().collect();
+        order.sort_by_key(|i| blueprint.recipes[*i].robot != target);
+
+        Self {
+            blueprint,
+            minutes,
+            target,
+            max_cost,
+            order,
+        }
+    }
+
+    /// Whether another robot for the resource could still be of any use
+    fn useful(&self, resource: usize, state: &State) -> bool {
+        if resource == self.target {
+            return true;
+        }
+
+        // Stock and robots already cover the most that can be spent until the end
+        let max = self.max_cost[resource];
+        let (stock, robots) = (state.stock[resource], state.robots[resource]);
+
+        robots < max && stock + robots * state.time < max * state.time
+    }
+
+    /// Minutes to wait until the recipe is affordable, `None` if it never will be
+    fn wait(&self, recipe: usize, state: &State) -> Option<usize> {
+        let cost = &self.blueprint.recipes[recipe].cost;
+        let mut wait = 0;
+
+        for (resource, needed) in cost.iter().enumerate() {
+            let missing = needed.saturating_sub(state.stock[resource]);
+            let robots = state.robots[resource];
+
+            match (missing, robots) {
+                (0, _) => {}
+                (_, 0) => return None,
+                (missing, robots) => wait = wait.max(missing.div_ceil(robots)),
+            }
+        }
+
+        Some(wait)
+    }
+
+    /// Target collected if every minute left built one robot of every kind that was
+    /// affordable, each recipe paying from its own copy of the resources
+    fn upper_bound(&self, state: &State) -> usize {
+        let recipes = &self.blueprint.recipes;
+        let mut stock = state.stock.clone();
+        let mut robots = state.robots.clone();
+        let mut pools: Vec<Vec<usize>> = vec![state.stock.clone(); recipes.len()];
+
+        for _ in 0..state.time {
+            let built: Vec<bool> = recipes
+                .iter()
+                .zip(pools.iter_mut())
+                .map(|(recipe, pool)| {
+                    let affordable = pool
+                        .iter()
+                        .zip(&recipe.cost)
+                        .all(|(have, cost)| have >= cost);
+                    if affordable {
+                        pool.iter_mut()
+                            .zip(&recipe.cost)
+                            .for_each(|(have, cost)| *have -= cost);
+                    }
+
+                    affordable
+                })
+                .collect();
+
+            for (resource, amount) in robots.iter().enumerate() {
+                stock[resource] += amount;
+                pools.iter_mut().for_each(|pool| pool[resource] += amount);
+            }
+
+            for (recipe, _) in recipes.iter().zip(built).filter(|(_, built)| *built) {
+                robots[recipe.robot] += 1;
+            }
+        }
+
+        stock[self.target]
+    }
+
+    fn search(&self, state: &State, builds: &mut Vec<(usize, usize)>, best: &mut Plan) {
+        // Build nothing else
+        let idle = state.stock[self.target] + state.robots[self.target] * state.time;
+        if idle > best.geodes || best.builds.is_empty() && idle == best.geodes {
+            *best = Plan {
+                geodes: idle,
+                builds: builds.clone(),
+            };
+        }
+
+        if self.upper_bound(state) <= best.geodes {
+            return;
+        }
+
+        for &i in self.order.iter() {
+            let recipe = &self.blueprint.recipes[i];
+            if !self.useful(recipe.robot, state) {
+                continue;
+            }
+
+            // The robot must be ready with at least a minute left to collect
+            let wait = match self.wait(i, state) {
+                Some(wait) if wait + 1 < state.time => wait,
+                _ => continue,
+            };
+
+            // Building takes a minute, the robot only collects from the next one
+            let mut next = state.clone();
+            next.collect_for(wait + 1);
+            next.build(recipe);
+
+            builds.push((self.minutes - state.time + wait + 1, i));
+            self.search(&next, builds, best);
+            builds.pop();
+        }
+    }
+
+    pub fn best(&self, initial_state: &State) -> Plan {
+        let mut best = Plan::default();
+        self.search(initial_state, &mut vec![], &mut best);
+
+        best
+    }
+
+    fn robot_name(&self, resource: usize) -> String {
+        match self.blueprint.resources[resource].as_str() {
+            "geode" => "geode-cracking".to_string(),
+            name => format!("{}-collecting", name),
+        }
+    }
+
+    /// Minute by minute account of a plan, like the puzzle's walkthrough.
+    ///
+    /// Panics if a robot in the plan can't be afforded when it's built.
+    pub fn walkthrough(&self, initial_state: &State, plan: &Plan) -> String {
+        let resources = &self.blueprint.resources;
+        let mut state = initial_state.clone();
+        let mut text = String::new();
+        let plural = |n: usize| if n == 1 { "" } else { "s" };
+
+        for minute in 1..=self.minutes {
+            let build = plan
+                .builds
+                .iter()
+                .find(|(at, _)| *at == minute)
+                .map(|(_, i)| &self.blueprint.recipes[*i]);
+
+            if minute > 1 {
+                text.push('\n');
+            }
+            writeln!(text, "== Minute {} ==", minute).unwrap();
+
+            if let Some(recipe) = build {
+                let name = self.robot_name(recipe.robot);
+                assert!(
+                    state.can_build(recipe),
+                    "Can't build {} on minute {}",
+                    name,
+                    minute
+                );
+
+                let spent: Vec<String> = recipe
+                    .cost
+                    .iter()
+                    .zip(resources)
+                    .filter(|(amount, _)| **amount > 0)
+                    .map(|(amount, resource)| format!("{} {}", amount, resource))
+                    .collect();
+                let article = match name.starts_with(['a', 'e', 'i', 'o', 'u']) {
+                    true => "an",
+                    false => "a",
+                };
+
+                writeln!(
+                    text,
+                    "Spend {} to start building {} {} robot.",
+                    spent.join(" and "),
+                    article,
+                    name
+                )
+                .unwrap();
+                state.consume_resources(recipe);
+            }
+
+            let robots = state.robots.clone();
+            state.collect_for(1);
+
+            for (resource, &count) in robots.iter().enumerate() {
+                if count == 0 {
+                    continue;
+                }
+
+                let amount = state.stock[resource];
+                let verb = if count == 1 { "s" } else { "" };
+                let line = match resources[resource].as_str() {
+                    "geode" => format!(
+                        "{} geode-cracking robot{} crack{} {} geode{}; you now have {} open geode{}.",
+                        count,
+                        plural(count),
+                        verb,
+                        count,
+                        plural(count),
+                        amount,
+                        plural(amount)
+                    ),
+                    name => format!(
+                        "{} {} robot{} collect{} {} {}; you now have {} {}.",
+                        count,
+                        self.robot_name(resource),
+                        plural(count),
+                        verb,
+                        count,
+                        name,
+                        amount,
+                        name
+                    ),
+                };
+                writeln!(text, "{}", line).unwrap();
+            }
+
+            if let Some(recipe) = build {
+                state.add_robot(recipe);
+                writeln!(
+                    text,
+                    "The new {} robot is ready; you now have {} of them.",
+                    self.robot_name(recipe.robot),
+                    state.robots[recipe.robot]
+                )
+                .unwrap();
+            }
+        }
+
+        assert_eq!(
+            state.stock[self.target], plan.geodes,
+            "Plan doesn't open as many geodes"
+        );
+
+        text
+    }
+}
@@ -1,81 +1,252 @@
-use std::collections::VecDeque;
+mod mixer;
 
-fn skip_take<'a, T>(n: usize, zero: T) -> &'a (usize, i64)
-where
-    T: Iterator<Item = &'a (usize, i64)>,
-{
-    let result = zero.skip(n).take(1).last().unwrap();
+use mixer::Mixer;
 
-    result
+/// Grove coordinates: the numbers found at some offsets after `0`
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Coordinates {
+    values: Vec<i64>,
+    sum: i64,
 }
 
-fn sort_list(numbers: &mut VecDeque<(usize, i64)>, count: usize) {
-    for _ in 0..count {
-        for i in 0..numbers.len() {
-            // Find the index
-            let idx = numbers
-                .iter()
-                .enumerate()
-                .find_map(|(pos, (j, _))| (i == *j).then_some(pos))
-                .unwrap();
+/// Multiplies every number by `key`, mixes them `rounds` times and reads the numbers
+/// at each of the `offsets` after `0`, wrapping around the list
+fn decrypt(numbers: &[i64], key: i64, rounds: usize, offsets: &[usize]) -> Coordinates {
+    let mut mixer = Mixer::new(numbers.iter().map(|it| it * key).collect());
+    mixer.mix(rounds);
 
-            // Bring the number to the front
-            numbers.rotate_left(idx);
-
-            // Remove the number
-            let (j, v) = numbers.pop_front().unwrap();
+    let order = mixer.order();
+    let zero = order
+        .iter()
+        .position(|it| *it == 0)
+        .expect("No zero in the list");
+    let values: Vec<i64> = offsets
+        .iter()
+        .map(|offset| order[(zero + offset) % order.len()])
+        .collect();
 
-            // Calculate the new position
-            let d = v.rem_euclid(numbers.len() as i64) as usize;
+    Coordinates {
+        sum: values.iter().sum(),
+        values,
+    }
+}
 
-            // Move to that position
-            numbers.rotate_left(d);
+struct Options {
+    input: Option<String>,
+    key: i64,
+    rounds: usize,
+    offsets: Vec<usize>,
+}
 
-            // Add the number
-            numbers.push_front((j, v));
+impl Options {
+    /// Reads `--input <path>`, `--key <n>`, `--rounds <n>` and `--offsets <a,b,...>`,
+    /// the key and rounds only apply to part 2
+    fn from_args(args: impl Iterator<Item = String>) -> Self {
+        let mut options = Self {
+            input: None,
+            key: 811589153,
+            rounds: 10,
+            offsets: vec![1000, 2000, 3000],
+        };
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            let value = args
+                .next()
+                .unwrap_or_else(|| panic!("Missing value after {}", arg));
+
+            match arg.as_str() {
+                "--input" => options.input = Some(value),
+                "--key" => options.key = value.parse().expect("Invalid key"),
+                "--rounds" => options.rounds = value.parse().expect("Invalid rounds"),
+                "--offsets" => {
+                    options.offsets = value
+                        .split(',')
+                        .map(|it| it.trim().parse().expect("Invalid offset"))
+                        .collect()
+                }
+                _ => panic!("Unknown argument: {}", arg),
+            }
         }
+
+        options
     }
 }
 
 fn main() {
-    let input = include_str!("../input");
+    let options = Options::from_args(std::env::args().skip(1));
+    let input = match &options.input {
+        Some(path) => std::fs::read_to_string(path)
+            .unwrap_or_else(|err| panic!("Can't read {}: {}", path, err)),
+        None => include_str!("../input").to_string(),
+    };
     let numbers: Vec<i64> = input.lines().map(|it| it.parse().unwrap()).collect();
 
     // Part 1
-    let mut list: VecDeque<(usize, i64)> = VecDeque::from(numbers.clone())
-        .into_iter()
-        .enumerate()
-        .collect();
+    let part1 = decrypt(&numbers, 1, 1, &options.offsets);
+    println!("Part 1: {:?} -> {}", part1.values, part1.sum);
 
-    sort_list(&mut list, 1);
-    let zero = list.iter().cycle().skip_while(|it| it.1 != 0);
+    // Part 2
+    let part2 = decrypt(&numbers, options.key, options.rounds, &options.offsets);
+    println!("Part 2: {:?} -> {}", part2.values, part2.sum);
+}
 
-    let item_1000 = skip_take(1000, zero.clone());
-    let item_2000 = skip_take(2000, zero.clone());
-    let item_3000 = skip_take(3000, zero.clone());
-    let sum: i64 = vec![item_1000, item_2000, item_3000]
-        .iter()
-        .map(|it| it.1)
-        .sum();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+
+    /// Mixes by rotating the whole list around every number, kept to check `Mixer` against
+    fn sort_list(numbers: &mut VecDeque<(usize, i64)>, count: usize) {
+        for _ in 0..count {
+            for i in 0..numbers.len() {
+                // Find the index
+                let idx = numbers
+                    .iter()
+                    .enumerate()
+                    .find_map(|(pos, (j, _))| (i == *j).then_some(pos))
+                    .unwrap();
+
+                // Bring the number to the front
+                numbers.rotate_left(idx);
+
+                // Remove the number
+                let (j, v) = numbers.pop_front().unwrap();
+
+                // Calculate the new position
+                let d = v.rem_euclid(numbers.len() as i64) as usize;
+
+                // Move to that position
+                numbers.rotate_left(d);
+
+                // Add the number
+                numbers.push_front((j, v));
+            }
+        }
+    }
 
-    println!("Part 1: {sum}");
+    /// Xorshift, enough to get the same pseudo-random lists on every run
+    fn random_numbers(seed: u64, len: usize, max: i64) -> Vec<i64> {
+        let mut state = seed;
 
-    // Part 2
-    let numbers: Vec<i64> = numbers.iter().map(|it| *it as i64 * 811589153).collect();
-    let mut list: VecDeque<(usize, i64)> = VecDeque::from(numbers.clone())
-        .into_iter()
-        .enumerate()
-        .collect();
-    sort_list(&mut list, 10);
-    let zero = list.iter().cycle().skip_while(|it| it.1 != 0);
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
 
-    let item_1000 = skip_take(1000, zero.clone());
-    let item_2000 = skip_take(2000, zero.clone());
-    let item_3000 = skip_take(3000, zero.clone());
-    let sum: i64 = vec![item_1000, item_2000, item_3000]
-        .iter()
-        .map(|it| it.1)
-        .sum();
+                (state % (2 * max as u64 + 1)) as i64 - max
+            })
+            .collect()
+    }
+
+    /// Original indices in mixed order, starting from the first number
+    fn reference(numbers: &[i64], rounds: usize) -> Vec<usize> {
+        let mut list: VecDeque<(usize, i64)> = numbers.iter().copied().enumerate().collect();
+        sort_list(&mut list, rounds);
 
-    println!("Part 2: {sum}");
+        let ids: Vec<usize> = list.iter().map(|it| it.0).collect();
+        starting_from_first(ids)
+    }
+
+    /// Lists are circular, so they're compared starting from the first number
+    fn starting_from_first(mut ids: Vec<usize>) -> Vec<usize> {
+        let start = ids.iter().position(|it| *it == 0).unwrap_or(0);
+        ids.rotate_left(start);
+
+        ids
+    }
+
+    #[test]
+    fn example_mix() {
+        let numbers: Vec<i64> = include_str!("../example")
+            .lines()
+            .map(|it| it.parse().unwrap())
+            .collect();
+        let mut mixer = Mixer::new(numbers);
+        mixer.mix(1);
+
+        let mut order = mixer.order();
+        let zero = order.iter().position(|it| *it == 0).unwrap();
+        order.rotate_left(zero);
+
+        assert_eq!(order, vec![0, 3, -2, 1, 2, -3, 4]);
+    }
+
+    #[test]
+    fn matches_rotations() {
+        for seed in 1..40 {
+            let len = 1 + (seed as usize * 7) % 60;
+            let max = [3, 50, 10_000, 811_589_153 * 5000][seed as usize % 4];
+            let numbers = random_numbers(seed, len, max);
+
+            for rounds in [1, 3] {
+                let mut mixer = Mixer::new(numbers.clone());
+                mixer.mix(rounds);
+
+                assert_eq!(
+                    starting_from_first(mixer.order_ids()),
+                    reference(&numbers, rounds),
+                    "{:?} mixed {} times",
+                    numbers,
+                    rounds
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn matches_rotations_on_input() {
+        let numbers: Vec<i64> = include_str!("../input")
+            .lines()
+            .take(700)
+            .map(|it| it.parse().unwrap())
+            .collect();
+
+        let mut mixer = Mixer::new(numbers.clone());
+        mixer.mix(2);
+
+        assert_eq!(
+            starting_from_first(mixer.order_ids()),
+            reference(&numbers, 2)
+        );
+    }
+
+    #[test]
+    fn example_decrypt() {
+        let numbers: Vec<i64> = include_str!("../example")
+            .lines()
+            .map(|it| it.parse().unwrap())
+            .collect();
+        let offsets = [1000, 2000, 3000];
+
+        assert_eq!(
+            decrypt(&numbers, 1, 1, &offsets),
+            Coordinates {
+                values: vec![4, -3, 2],
+                sum: 3,
+            }
+        );
+        assert_eq!(
+            decrypt(&numbers, 811589153, 10, &offsets),
+            Coordinates {
+                values: vec![811589153, 2434767459, -1623178306],
+                sum: 1623178306,
+            }
+        );
+
+        // Offsets wrap around the list, `0` is the number itself
+        assert_eq!(decrypt(&numbers, 1, 1, &[0, 1, 8]).values, vec![0, 3, 3]);
+    }
+
+    #[test]
+    fn options_args() {
+        let args = ["--key", "3", "--rounds", "2", "--offsets", "1, 5"];
+        let options = Options::from_args(args.map(String::from).into_iter());
+
+        assert_eq!(options.key, 3);
+        assert_eq!(options.rounds, 2);
+        assert_eq!(options.offsets, vec![1, 5]);
+        assert_eq!(options.input, None);
+    }
 }
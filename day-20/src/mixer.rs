@@ -0,0 +1,121 @@
+/// Circular list of numbers split in buckets of about `√n` elements.
+///
+/// Finding where a number is only needs to count the elements of the buckets before
+/// its own, and moving it only shifts the elements of two buckets, so a whole
+/// round of mixing takes `O(n√n)`.
+pub struct Mixer {
+    values: Vec<i64>,
+    buckets: Vec<Vec<usize>>,
+    /// Bucket every number, by original index, is in
+    bucket_of: Vec<usize>,
+    bucket_size: usize,
+}
+
+impl Mixer {
+    pub fn new(values: Vec<i64>) -> Self {
+        let bucket_size = ((values.len() as f64).sqrt() as usize).max(1);
+        let mut mixer = Self {
+            bucket_of: vec![0; values.len()],
+            values,
+            buckets: vec![],
+            bucket_size,
+        };
+
+        mixer.rebuild((0..mixer.values.len()).collect());
+        mixer
+    }
+
+    /// Splits the numbers in `order` in evenly sized buckets again
+    fn rebuild(&mut self, order: Vec<usize>) {
+        self.buckets = order
+            .chunks(self.bucket_size)
+            .map(|chunk| chunk.to_vec())
+            .collect();
+
+        for (b, bucket) in self.buckets.iter().enumerate() {
+            for &id in bucket {
+                self.bucket_of[id] = b;
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Current position of the number originally at `id`
+    pub fn position(&self, id: usize) -> usize {
+        let b = self.bucket_of[id];
+        let before: usize = self.buckets[..b].iter().map(|it| it.len()).sum();
+
+        before + self.buckets[b].iter().position(|it| *it == id).unwrap()
+    }
+
+    fn remove_at(&mut self, mut pos: usize) -> usize {
+        for bucket in self.buckets.iter_mut() {
+            if pos < bucket.len() {
+                return bucket.remove(pos);
+            }
+            pos -= bucket.len();
+        }
+
+        panic!("Position out of the list");
+    }
+
+    fn insert_at(&mut self, mut pos: usize, id: usize) {
+        let last = self.buckets.len() - 1;
+
+        for (b, bucket) in self.buckets.iter_mut().enumerate() {
+            if pos <= bucket.len() && (pos < bucket.len() || b == last) {
+                bucket.insert(pos, id);
+                self.bucket_of[id] = b;
+
+                // Keep buckets small enough for lookups to stay cheap
+                if bucket.len() > 2 * self.bucket_size {
+                    self.rebuild(self.order_ids());
+                }
+
+                return;
+            }
+            pos -= bucket.len();
+        }
+
+        panic!("Position out of the list");
+    }
+
+    /// Moves the number originally at `id` forward by its value, wrapping around
+    pub fn move_by_value(&mut self, id: usize) {
+        if self.len() < 2 {
+            return;
+        }
+
+        let from = self.position(id);
+        self.remove_at(from);
+
+        let to = (from as i64 + self.values[id]).rem_euclid(self.len() as i64 - 1);
+        self.insert_at(to as usize, id);
+    }
+
+    /// Moves every number once, in their original order
+    pub fn mix(&mut self, rounds: usize) {
+        for _ in 0..rounds {
+            for id in 0..self.len() {
+                self.move_by_value(id);
+            }
+        }
+    }
+
+    /// Original indices of the numbers in their current order
+    pub fn order_ids(&self) -> Vec<usize> {
+        self.buckets.iter().flatten().copied().collect()
+    }
+
+    /// Numbers in their current order, starting from the first position
+    pub fn order(&self) -> Vec<i64> {
+        self.buckets
+            .iter()
+            .flatten()
+            .map(|id| self.values[*id])
+            .collect()
+    }
+}
@@ -0,0 +1,338 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::Display;
+
+use crate::linear::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Op {
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Op::Add => "+",
+            Op::Sub => "-",
+            Op::Mul => "*",
+            Op::Div => "/",
+        }
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Op::Add | Op::Sub => 1,
+            Op::Mul | Op::Div => 2,
+        }
+    }
+}
+
+impl From<&str> for Op {
+    fn from(input: &str) -> Self {
+        match input {
+            "+" => Op::Add,
+            "-" => Op::Sub,
+            "*" => Op::Mul,
+            "/" => Op::Div,
+            _ => unreachable!("Failed to parse operator: {}", input),
+        }
+    }
+}
+
+/// What a monkey yells, referring to other monkeys by name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Job {
+    Number(i64),
+    Operation(String, Op, String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Monkey {
+    pub name: String,
+    pub job: Job,
+}
+
+impl From<&str> for Monkey {
+    fn from(input: &str) -> Self {
+        let (name, job) = input
+            .split_once(": ")
+            .unwrap_or_else(|| panic!("Failed to parse line: {}", input));
+        let parts: Vec<&str> = job.split(' ').collect();
+
+        let job = match parts[..] {
+            [value] => Job::Number(value.parse().unwrap()),
+            [lhs, op, rhs] => Job::Operation(lhs.to_string(), op.into(), rhs.to_string()),
+            _ => unreachable!("Failed to parse line: {}", input),
+        };
+
+        Monkey {
+            name: name.to_string(),
+            job,
+        }
+    }
+}
+
+/// Monkey job with the monkeys it waits for as indices in the arena
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Expr {
+    Number(i64),
+    Binary { op: Op, lhs: usize, rhs: usize },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TreeError {
+    /// Monkey waiting for one nobody knows about
+    UnknownMonkey(String),
+    /// Monkeys waiting on each other in a loop, in order
+    Cycle(Vec<String>),
+}
+
+impl Display for TreeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TreeError::UnknownMonkey(name) => write!(f, "unknown monkey {}", name),
+            TreeError::Cycle(names) => write!(f, "monkeys wait in a loop: {}", names.join(" -> ")),
+        }
+    }
+}
+
+/// Every monkey's job stored in an arena, evaluated from `root`.
+///
+/// Values of monkeys not depending on `humn` never change, so they're remembered
+/// the first time they're needed.
+pub struct Tree {
+    names: Vec<String>,
+    exprs: Vec<Expr>,
+    pub root: usize,
+    pub human: usize,
+    /// Whether a monkey's value depends on what `humn` yells
+    depends_on_human: Vec<bool>,
+    memo: RefCell<Vec<Option<Rational>>>,
+}
+
+impl Tree {
+    pub fn new(monkeys: &[Monkey], root: &str, human: &str) -> Result<Self, TreeError> {
+        let index: HashMap<String, usize> = monkeys
+            .iter()
+            .enumerate()
+            .map(|(i, it)| (it.name.clone(), i))
+            .collect();
+        let find = |name: &str| {
+            index
+                .get(name)
+                .copied()
+                .ok_or_else(|| TreeError::UnknownMonkey(name.to_string()))
+        };
+
+        let exprs = monkeys
+            .iter()
+            .map(|monkey| match &monkey.job {
+                Job::Number(value) => Ok(Expr::Number(*value)),
+                Job::Operation(lhs, op, rhs) => Ok(Expr::Binary {
+                    op: *op,
+                    lhs: find(lhs)?,
+                    rhs: find(rhs)?,
+                }),
+            })
+            .collect::<Result<Vec<Expr>, TreeError>>()?;
+
+        let mut tree = Self {
+            names: monkeys.iter().map(|it| it.name.clone()).collect(),
+            root: find(root)?,
+            human: find(human)?,
+            depends_on_human: vec![false; exprs.len()],
+            memo: RefCell::new(vec![None; exprs.len()]),
+            exprs,
+        };
+
+        for id in tree.post_order()? {
+            tree.depends_on_human[id] = match tree.exprs[id] {
+                _ if id == tree.human => true,
+                Expr::Number(_) => false,
+                Expr::Binary { lhs, rhs, .. } => {
+                    tree.depends_on_human[lhs] || tree.depends_on_human[rhs]
+                }
+            };
+        }
+
+        Ok(tree)
+    }
+
+    pub fn parse(input: &str) -> Result<Self, TreeError> {
+        let monkeys: Vec<Monkey> = input.lines().map(Monkey::from).collect();
+
+        Self::new(&monkeys, "root", "humn")
+    }
+
+    #[cfg(test)]
+    pub fn id(&self, name: &str) -> Option<usize> {
+        self.names.iter().position(|it| it == name)
+    }
+
+    #[cfg(test)]
+    pub fn expr(&self, id: usize) -> Expr {
+        self.exprs[id]
+    }
+
+    /// Monkeys reachable from `root`, every one after those it waits for
+    fn post_order(&self) -> Result<Vec<usize>, TreeError> {
+        #[derive(Clone, Copy, PartialEq)]
+        enum Mark {
+            New,
+            Waiting,
+            Done,
+        }
+
+        let mut marks = vec![Mark::New; self.exprs.len()];
+        let mut order = vec![];
+        // Monkey and whether the ones it waits for were already pushed
+        let mut stack = vec![(self.root, false)];
+
+        while let Some((id, expanded)) = stack.pop() {
+            if expanded {
+                marks[id] = Mark::Done;
+                order.push(id);
+                continue;
+            }
+
+            match marks[id] {
+                Mark::Done => continue,
+                Mark::Waiting => {
+                    // Monkeys still waiting are exactly the ones on the current path
+                    let path: Vec<usize> = stack
+                        .iter()
+                        .filter(|(it, expanded)| *expanded && marks[*it] == Mark::Waiting)
+                        .map(|(it, _)| *it)
+                        .collect();
+                    let start = path.iter().position(|it| *it == id).unwrap();
+                    let mut names: Vec<String> = path[start..]
+                        .iter()
+                        .map(|it| self.names[*it].clone())
+                        .collect();
+                    names.push(self.names[id].clone());
+
+                    return Err(TreeError::Cycle(names));
+                }
+                Mark::New => {}
+            }
+
+            marks[id] = Mark::Waiting;
+            stack.push((id, true));
+
+            if let Expr::Binary { lhs, rhs, .. } = self.exprs[id] {
+                stack.push((rhs, false));
+                stack.push((lhs, false));
+            }
+        }
+
+        Ok(order)
+    }
+
+    /// Value of a monkey when `humn` yells `human`
+    pub fn eval(&self, id: usize, human: Linear) -> Result<Linear, SolveError> {
+        if id == self.human {
+            return Ok(human);
+        }
+
+        if let Some(value) = self.memo.borrow()[id] {
+            return Ok(Linear::constant(value));
+        }
+
+        let value = match self.exprs[id] {
+            Expr::Number(value) => Linear::constant(Rational::integer(value as i128)),
+            Expr::Binary { op, lhs, rhs } => {
+                let left = self.eval(lhs, human)?;
+                let right = self.eval(rhs, human)?;
+                left.apply(op, right, &self.names[id])?
+            }
+        };
+
+        if !self.depends_on_human[id] {
+            self.memo.borrow_mut()[id] = Some(value.b);
+        }
+
+        Ok(value)
+    }
+
+    /// Value of `root` when `humn` yells the number in its own job
+    pub fn value(&self) -> Result<Rational, SolveError> {
+        // Monkeys `humn` waits for can't wait for `humn` in turn, there are no loops
+        let human = match self.exprs[self.human] {
+            Expr::Number(value) => Linear::constant(Rational::integer(value as i128)),
+            Expr::Binary { op, lhs, rhs } => {
+                let left = self.eval(lhs, Linear::human())?;
+                let right = self.eval(rhs, Linear::human())?;
+                left.apply(op, right, &self.names[self.human])?
+            }
+        };
+
+        Ok(self.eval(self.root, human)?.b)
+    }
+
+    /// Number `humn` has to yell for both sides of `root` to be equal
+    pub fn solve_human(&self) -> Result<i128, SolveError> {
+        match self.exprs[self.root] {
+            Expr::Binary { lhs, rhs, .. } => {
+                let left = self.eval(lhs, Linear::human())?;
+                let right = self.eval(rhs, Linear::human())?;
+
+                left.solve_equal(right)
+            }
+            Expr::Number(_) => Err(SolveError::NoSolution),
+        }
+    }
+
+    /// Monkey's job as infix text, with parts not depending on `humn` folded into
+    /// their value when `fold` is set
+    pub fn infix(&self, id: usize, fold: bool) -> String {
+        if id == self.human {
+            return self.names[id].clone();
+        }
+
+        if fold && !self.depends_on_human[id] {
+            if let Ok(value) = self.eval(id, Linear::human()) {
+                return value.b.to_string();
+            }
+        }
+
+        match self.exprs[id] {
+            Expr::Number(value) => value.to_string(),
+            Expr::Binary { op, lhs, rhs } => {
+                let side = |child: usize, right: bool| {
+                    let text = self.infix(child, fold);
+                    let needs_parens = match self.exprs[child] {
+                        _ if child == self.human => false,
+                        _ if fold && !self.depends_on_human[child] => false,
+                        Expr::Number(_) => false,
+                        Expr::Binary { op: inner, .. } => {
+                            inner.precedence() < op.precedence()
+                                || right
+                                    && inner.precedence() == op.precedence()
+                                    && matches!(op, Op::Sub | Op::Div)
+                        }
+                    };
+
+                    match needs_parens {
+                        true => format!("({})", text),
+                        false => text,
+                    }
+                };
+
+                format!("{} {} {}", side(lhs, false), op.symbol(), side(rhs, true))
+            }
+        }
+    }
+
+    /// The equation `root` checks, both of its sides being equal
+    pub fn equation(&self) -> String {
+        match self.exprs[self.root] {
+            Expr::Binary { lhs, rhs, .. } => {
+                format!("{} = {}", self.infix(lhs, true), self.infix(rhs, true))
+            }
+            Expr::Number(value) => value.to_string(),
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::fmt::Display;
+use std::ops::{Add, Div, Mul, Sub};
+
+use crate::expr::Op;
+
+/// Exact fraction, always stored with a positive denominator and in lowest terms
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rational {
+    pub num: i128,
+    pub den: i128,
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    if b == 0 {
+        a.abs()
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+impl Rational {
+    pub fn new(num: i128, den: i128) -> Self {
+        assert!(den != 0, "Zero denominator");
+
+        let g = gcd(num, den).max(1) * den.signum();
+        Self {
+            num: num / g,
+            den: den / g,
+        }
+    }
+
+    pub fn integer(value: i128) -> Self {
+        Self::new(value, 1)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.num == 0
+    }
+
+    pub fn to_integer(self) -> Option<i128> {
+        (self.den == 1).then_some(self.num)
+    }
+}
+
+impl Display for Rational {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.den {
+            1 => write!(f, "{}", self.num),
+            den => write!(f, "{}/{}", self.num, den),
+        }
+    }
+}
+
+impl Add for Rational {
+    type Output = Rational;
+    fn add(self, rhs: Self) -> Self {
+        Self::new(self.num * rhs.den + rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl Sub for Rational {
+    type Output = Rational;
+    fn sub(self, rhs: Self) -> Self {
+        Self::new(self.num * rhs.den - rhs.num * self.den, self.den * rhs.den)
+    }
+}
+
+impl Mul for Rational {
+    type Output = Rational;
+    fn mul(self, rhs: Self) -> Self {
+        Self::new(self.num * rhs.num, self.den * rhs.den)
+    }
+}
+
+impl Div for Rational {
+    type Output = Rational;
+    fn div(self, rhs: Self) -> Self {
+        Self::new(self.num * rhs.den, self.den * rhs.num)
+    }
+}
+
+/// `a * humn + b`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Linear {
+    pub a: Rational,
+    pub b: Rational,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SolveError {
+    /// Monkey multiplying `humn` by itself or dividing by it
+    NonLinear(String),
+    /// Monkey dividing by zero
+    DivisionByZero(String),
+    /// Both sides are always different
+    NoSolution,
+    /// Both sides are always equal, whatever `humn` yells
+    AnySolution,
+    /// The only solution isn't a whole number
+    NotInteger(Rational),
+}
+
+impl Display for SolveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SolveError::NonLinear(name) => write!(f, "{} isn't linear in humn", name),
+            SolveError::DivisionByZero(name) => write!(f, "{} divides by zero", name),
+            SolveError::NoSolution => write!(f, "no number makes both sides equal"),
+            SolveError::AnySolution => write!(f, "every number makes both sides equal"),
+            SolveError::NotInteger(value) => write!(f, "the only solution is {}", value),
+        }
+    }
+}
+
+impl Linear {
+    pub fn constant(value: Rational) -> Self {
+        Self {
+            a: Rational::integer(0),
+            b: value,
+        }
+    }
+
+    pub fn human() -> Self {
+        Self {
+            a: Rational::integer(1),
+            b: Rational::integer(0),
+        }
+    }
+
+    fn is_constant(&self) -> bool {
+        self.a.is_zero()
+    }
+
+    /// Applies the monkey `name`'s operation, as long as the result stays linear
+    pub fn apply(self, op: Op, rhs: Linear, name: &str) -> Result<Linear, SolveError> {
+        let result = match op {
+            Op::Add => Linear {
+                a: self.a + rhs.a,
+                b: self.b + rhs.b,
+            },
+            Op::Sub => Linear {
+                a: self.a - rhs.a,
+                b: self.b - rhs.b,
+            },
+            Op::Mul if rhs.is_constant() => Linear {
+                a: self.a * rhs.b,
+                b: self.b * rhs.b,
+            },
+            Op::Mul if self.is_constant() => Linear {
+                a: rhs.a * self.b,
+                b: rhs.b * self.b,
+            },
+            Op::Div if rhs.is_constant() && rhs.b.is_zero() => {
+                return Err(SolveError::DivisionByZero(name.to_string()))
+            }
+            Op::Div if rhs.is_constant() => Linear {
+                a: self.a / rhs.b,
+                b: self.b / rhs.b,
+            },
+            Op::Mul | Op::Div => return Err(SolveError::NonLinear(name.to_string())),
+        };
+
+        Ok(result)
+    }
+
+    /// Value of `humn` making both sides equal
+    pub fn solve_equal(self, rhs: Linear) -> Result<i128, SolveError> {
+        let a = self.a - rhs.a;
+        let b = rhs.b - self.b;
+
+        match (a.is_zero(), b.is_zero()) {
+            (true, true) => Err(SolveError::AnySolution),
+            (true, false) => Err(SolveError::NoSolution),
+            _ => {
+                let x = b / a;
+                x.to_integer().ok_or(SolveError::NotInteger(x))
+            }
+        }
+    }
+}
@@ -1,137 +1,43 @@
-use std::collections::HashMap;
-
-#[derive(Debug, Clone, PartialEq, PartialOrd)]
-struct Monkey {
-    name: String,
-    value: Option<f64>,
-    lhs: Option<String>,
-    rhs: Option<String>,
-    op: Option<String>,
-}
-
-impl From<&str> for Monkey {
-    fn from(input: &str) -> Self {
-        let parts: Vec<&str> = input.split(": ").collect();
-        let name = parts.get(0).unwrap().to_string();
-        let val = parts.get(1).unwrap();
-
-        let parts: Vec<&str> = val.split(" ").collect();
-        let mut value: Option<f64> = None;
-        let mut lhs: Option<String> = None;
-        let mut rhs: Option<String> = None;
-        let mut op: Option<String> = None;
-
-        match parts.len() {
-            1 => {
-                value = Some(parts.get(0).unwrap().parse::<f64>().unwrap());
-            }
-            3 => {
-                lhs = Some(parts.get(0).unwrap().to_string());
-                op = Some(parts.get(1).unwrap().to_string());
-                rhs = Some(parts.get(2).unwrap().to_string());
-            }
-            _ => unreachable!("Failed to parse line: {}", input),
-        }
+mod expr;
+mod linear;
 
-        Monkey {
-            name,
-            value,
-            lhs,
-            rhs,
-            op,
-        }
-    }
-}
-
-fn solve_equation(lhs: f64, op: &str, rhs: f64) -> f64 {
-    match op {
-        "+" => lhs + rhs,
-        "-" => lhs - rhs,
-        "*" => lhs * rhs,
-        "/" => lhs / rhs,
-        _ => unreachable!("Failed to parse operator: {}", op),
-    }
-}
-
-fn solve_value(monkeys: &HashMap<String, Monkey>, root: &Monkey, human: f64) -> f64 {
-    if root.name == "humn" {
-        return human;
-    }
-
-    match (&root.lhs, &root.op, &root.rhs) {
-        (None, None, None) => root.value.unwrap(),
-        (Some(lhs), Some(op), Some(rhs)) => {
-            let left = solve_value(monkeys, monkeys.get(lhs).unwrap(), human);
-            let right = solve_value(monkeys, monkeys.get(rhs).unwrap(), human);
-            solve_equation(left, &op, right)
-        }
-        _ => unreachable!("Failed to parse root: {:?}", root),
-    }
-}
+use expr::*;
 
 fn main() {
     let input = include_str!("../input");
-    let monkeys: HashMap<String, Monkey> = input
-        .lines()
-        .map(|it| Monkey::from(it))
-        .map(|it| (it.name.clone(), it))
-        .collect();
+    let tree = match Tree::parse(input) {
+        Ok(tree) => tree,
+        Err(err) => panic!("Invalid monkeys: {}", err),
+    };
 
     // Part 1
-    let human = monkeys.get("humn").unwrap();
-    let human_val = human.value.unwrap();
-    let val = solve_value(&monkeys, monkeys.get("root").unwrap(), human_val);
-
-    println!("Part 1: {}", val as i64);
+    match tree.value() {
+        Ok(value) => println!("Part 1: {}", value),
+        Err(err) => println!("Part 1: {}", err),
+    }
 
     // Part 2
-    let root = monkeys.get("root").unwrap();
-
-    // Resolve right
-    let rhs = monkeys.get(&root.rhs.clone().unwrap()).unwrap();
-    let right = solve_value(&monkeys, rhs, human_val);
-
-    let mut high = f32::MAX as f64;
-    let mut low = f32::MIN as f64;
-    let mut mid = 0f64;
-    while low < high {
-        let lhs = monkeys.get(&root.lhs.clone().unwrap()).unwrap();
-
-        // Resolve left
-        mid = (high + low) / 2f64;
-        let left = solve_value(&monkeys, lhs, mid);
-
-        if left > right {
-            low = mid;
-        } else if left < right {
-            high = mid;
-        }
-
-        // Assert match
-        let diff = left - right;
-        if diff > -1f64 && diff < 1f64 {
-            break;
-        }
+    println!("{}", tree.equation());
+    match tree.solve_human() {
+        Ok(value) => println!("Part 2: {}", value),
+        Err(err) => println!("Part 2: {}", err),
     }
-
-    println!("Part 2: {}", mid);
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::linear::*;
+
+    fn parse(input: &str) -> Tree {
+        Tree::parse(input).unwrap()
+    }
 
     #[test]
     fn example() {
-        let input = include_str!("../example");
-        let monkeys: HashMap<String, Monkey> = input
-            .lines()
-            .map(|it| Monkey::from(it))
-            .map(|it| (it.name.clone(), it))
-            .collect();
-        let val = solve_value(&monkeys, monkeys.get("root").unwrap(), 5f64);
-
-        assert_eq!(val, 152f64);
+        let tree = parse(include_str!("../example"));
+
+        assert_eq!(tree.value(), Ok(Rational::integer(152)));
     }
 
     #[test]
@@ -139,10 +45,7 @@ mod tests {
         let input: Monkey = "dbpl: 5".into();
         let expected = Monkey {
             name: "dbpl".to_string(),
-            value: Some(5f64),
-            op: None,
-            lhs: None,
-            rhs: None,
+            job: Job::Number(5),
         };
         assert_eq!(input, expected);
     }
@@ -151,11 +54,130 @@ mod tests {
         let input: Monkey = "sjmn: drzm * dbpl".into();
         let expected = Monkey {
             name: "sjmn".to_string(),
-            value: None,
-            op: Some("*".to_string()),
-            lhs: Some("drzm".to_string()),
-            rhs: Some("dbpl".to_string()),
+            job: Job::Operation("drzm".to_string(), Op::Mul, "dbpl".to_string()),
         };
         assert_eq!(input, expected);
     }
+
+    #[test]
+    fn arena() {
+        let tree = parse(include_str!("../example"));
+        let (lhs, rhs) = (tree.id("pppw").unwrap(), tree.id("sjmn").unwrap());
+
+        assert_eq!(
+            tree.expr(tree.root),
+            Expr::Binary {
+                op: Op::Add,
+                lhs,
+                rhs
+            }
+        );
+        assert_eq!(tree.expr(tree.human), Expr::Number(5));
+        assert_eq!(tree.id("nope"), None);
+    }
+
+    #[test]
+    fn example_human() {
+        let tree = parse(include_str!("../example"));
+
+        assert_eq!(tree.solve_human(), Ok(301));
+    }
+
+    #[test]
+    fn input_human() {
+        let input = include_str!("../input");
+        let tree = parse(input);
+        let human = tree.solve_human().unwrap();
+
+        // Both sides are equal with exact arithmetic
+        let mut monkeys: Vec<Monkey> = input.lines().map(Monkey::from).collect();
+        let root = monkeys.iter_mut().find(|it| it.name == "root").unwrap();
+        if let Job::Operation(_, op, _) = &mut root.job {
+            *op = Op::Sub;
+        }
+        let tree = Tree::new(&monkeys, "root", "humn").unwrap();
+        let diff = tree.eval(tree.root, Linear::human()).unwrap();
+
+        assert_eq!(
+            diff.a * Rational::integer(human) + diff.b,
+            Rational::integer(0)
+        );
+    }
+
+    #[test]
+    fn human_errors() {
+        let tree = parse("root: humn + half\nhalf: one / two\none: 1\ntwo: 2\nhumn: 5");
+        assert_eq!(
+            tree.solve_human(),
+            Err(SolveError::NotInteger(Rational::new(1, 2)))
+        );
+
+        let tree = parse("root: sqr + one\nsqr: humn * humn\none: 1\nhumn: 5");
+        assert_eq!(
+            tree.solve_human(),
+            Err(SolveError::NonLinear("sqr".to_string()))
+        );
+
+        let tree = parse("root: same + one\nsame: humn - humn\none: 1\nhumn: 5");
+        assert_eq!(tree.solve_human(), Err(SolveError::NoSolution));
+
+        let tree =
+            parse("root: double + sum\ndouble: humn * two\nsum: humn + humn\ntwo: 2\nhumn: 5");
+        assert_eq!(tree.solve_human(), Err(SolveError::AnySolution));
+    }
+
+    #[test]
+    fn decreasing_human() {
+        // The left side goes down as `humn` goes up
+        let tree = parse("root: left + ten\nleft: zero - humn\nzero: 0\nten: 10\nhumn: 5");
+
+        assert_eq!(tree.solve_human(), Ok(-10));
+    }
+
+    #[test]
+    fn tree_errors() {
+        let err = Tree::parse("root: humn + lost\nhumn: 5").err();
+        assert_eq!(err, Some(TreeError::UnknownMonkey("lost".to_string())));
+
+        let err = Tree::parse("root: humn + a\na: b * two\nb: a - humn\ntwo: 2\nhumn: 5").err();
+        assert_eq!(
+            err,
+            Some(TreeError::Cycle(vec![
+                "a".to_string(),
+                "b".to_string(),
+                "a".to_string()
+            ]))
+        );
+
+        let err = Tree::parse("root: root + humn\nhumn: 5").err();
+        assert_eq!(
+            err,
+            Some(TreeError::Cycle(vec![
+                "root".to_string(),
+                "root".to_string()
+            ]))
+        );
+    }
+
+    #[test]
+    fn equation() {
+        let tree = parse(include_str!("../example"));
+
+        assert_eq!(tree.equation(), "(4 + 2 * (humn - 3)) / 4 = 150");
+        assert_eq!(tree.infix(tree.id("sjmn").unwrap(), false), "(32 - 2) * 5");
+
+        let tree =
+            parse("root: a - b\na: humn - c\nb: 1\nc: d - e\nd: 4\ne: humn / f\nf: 2\nhumn: 5");
+        assert_eq!(tree.equation(), "humn - (4 - humn / 2) = 1");
+    }
+
+    #[test]
+    fn memoised_constants() {
+        let tree = parse(include_str!("../example"));
+
+        // The same tree answers both parts, `humn` only changes its own path
+        assert_eq!(tree.value(), Ok(Rational::integer(152)));
+        assert_eq!(tree.solve_human(), Ok(301));
+        assert_eq!(tree.value(), Ok(Rational::integer(152)));
+    }
 }